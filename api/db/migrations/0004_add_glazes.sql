@@ -0,0 +1,26 @@
+CREATE TABLE glazes (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    cone_min TEXT,
+    cone_max TEXT,
+    surface TEXT CHECK (surface IN ('Gloss', 'Matte', 'Satin'))
+);
+
+ALTER TABLE works
+  ADD glaze_id INTEGER REFERENCES glazes (id) ON DELETE SET NULL;
+
+-- Create a glaze for every distinct description already in use, and point
+-- works at the glaze whose name matches their description exactly.
+INSERT INTO glazes (name)
+SELECT DISTINCT glaze_description
+FROM works
+WHERE glaze_description IS NOT NULL AND glaze_description != '';
+
+UPDATE works
+SET glaze_id = (
+    SELECT g.id
+    FROM glazes g
+    WHERE g.name = works.glaze_description
+)
+WHERE glaze_description IS NOT NULL;
@@ -0,0 +1,43 @@
+CREATE TABLE work_glazes (
+    id INTEGER PRIMARY KEY,
+    work_id INTEGER NOT NULL,
+    glaze_id INTEGER NOT NULL,
+    layer INTEGER NOT NULL,
+    method TEXT CHECK (method IN ('Dip', 'Pour', 'Brush', 'Spray')),
+    coats INTEGER NOT NULL DEFAULT 1,
+    area TEXT,
+    UNIQUE (work_id, layer),
+    FOREIGN KEY (work_id) REFERENCES works (id),
+    FOREIGN KEY (glaze_id) REFERENCES glazes (id) ON DELETE CASCADE
+);
+
+-- Existing single glazes become the first layer of their work.
+INSERT INTO work_glazes (work_id, glaze_id, layer)
+SELECT id, glaze_id, 1
+FROM works
+WHERE glaze_id IS NOT NULL;
+
+-- SQLite cannot drop a column that takes part in a foreign key, so rebuild
+-- `works` without `glaze_id`.
+CREATE TABLE works_new (
+    id INTEGER PRIMARY KEY,
+    project_id INTEGER,
+    name TEXT NOT NULL,
+    notes TEXT,
+    clay_id INTEGER,
+    glaze_description TEXT,
+    header_key TEXT,
+    thumbnail_key TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    is_multiple BOOLEAN,
+    FOREIGN KEY (project_id) REFERENCES projects (id),
+    FOREIGN KEY (clay_id) REFERENCES clays (id)
+);
+
+INSERT INTO works_new (id, project_id, name, notes, clay_id, glaze_description, header_key, thumbnail_key, created_at, is_multiple)
+SELECT id, project_id, name, notes, clay_id, glaze_description, header_key, thumbnail_key, created_at, is_multiple
+FROM works;
+
+DROP TABLE works;
+
+ALTER TABLE works_new RENAME TO works;
@@ -0,0 +1,9 @@
+CREATE TABLE glaze_ingredients (
+    id INTEGER PRIMARY KEY,
+    glaze_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    material TEXT NOT NULL,
+    percentage REAL NOT NULL,
+    is_addition BOOLEAN NOT NULL DEFAULT 0,
+    FOREIGN KEY (glaze_id) REFERENCES glazes (id) ON DELETE CASCADE
+);
@@ -0,0 +1,48 @@
+CREATE TABLE materials (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    loi REAL NOT NULL DEFAULT 0
+);
+
+CREATE TABLE material_oxides (
+    material_id INTEGER NOT NULL,
+    oxide TEXT NOT NULL,
+    percentage REAL NOT NULL,
+    PRIMARY KEY (material_id, oxide),
+    FOREIGN KEY (material_id) REFERENCES materials (id) ON DELETE CASCADE
+);
+
+INSERT INTO materials (id, name, loi) VALUES
+    (1, 'Silica', 0),
+    (2, 'EPK', 13.8),
+    (3, 'Whiting', 43.9),
+    (4, 'Custer Feldspar', 0.3),
+    (5, 'Nepheline Syenite', 0.7),
+    (6, 'Talc', 4.8),
+    (7, 'Dolomite', 47.7),
+    (8, 'Wollastonite', 0),
+    (9, 'Ferro Frit 3134', 0),
+    (10, 'Zinc Oxide', 0),
+    (11, 'Red Iron Oxide', 0);
+
+INSERT INTO material_oxides (material_id, oxide, percentage) VALUES
+    (1, 'SiO2', 100),
+    (2, 'SiO2', 45.7), (2, 'Al2O3', 37.4), (2, 'Fe2O3', 0.8), (2, 'TiO2', 0.4),
+    (2, 'CaO', 0.2), (2, 'MgO', 0.1), (2, 'K2O', 0.3), (2, 'Na2O', 0.1),
+    (3, 'CaO', 56.1),
+    (4, 'SiO2', 68.5), (4, 'Al2O3', 17.0), (4, 'K2O', 10.0), (4, 'Na2O', 3.0),
+    (4, 'CaO', 0.3), (4, 'Fe2O3', 0.1),
+    (5, 'SiO2', 60.2), (5, 'Al2O3', 23.6), (5, 'Na2O', 10.4), (5, 'K2O', 4.8),
+    (5, 'CaO', 0.3), (5, 'Fe2O3', 0.1),
+    (6, 'SiO2', 63.5), (6, 'MgO', 31.7),
+    (7, 'CaO', 30.4), (7, 'MgO', 21.9),
+    (8, 'SiO2', 51.7), (8, 'CaO', 48.3),
+    (9, 'SiO2', 46.5), (9, 'B2O3', 23.1), (9, 'CaO', 20.1), (9, 'Na2O', 10.3),
+    (10, 'ZnO', 100),
+    (11, 'Fe2O3', 100);
+
+ALTER TABLE works
+  ADD glaze_recipe TEXT;
+
+ALTER TABLE works
+  ADD glaze_umf TEXT;
@@ -0,0 +1,15 @@
+-- Coefficients of thermal expansion are stored in units of 10^-7 per degree
+-- Celsius.
+ALTER TABLE clays
+  ADD coe REAL;
+
+ALTER TABLE glazes
+  ADD coe REAL;
+
+CREATE TABLE glaze_oxides (
+    glaze_id INTEGER NOT NULL,
+    oxide TEXT NOT NULL,
+    percentage REAL NOT NULL,
+    PRIMARY KEY (glaze_id, oxide),
+    FOREIGN KEY (glaze_id) REFERENCES glazes (id) ON DELETE CASCADE
+);
@@ -0,0 +1,17 @@
+ALTER TABLE clays
+  ADD supplier TEXT;
+
+ALTER TABLE clays
+  ADD cone_min TEXT;
+
+ALTER TABLE clays
+  ADD cone_max TEXT;
+
+ALTER TABLE clays
+  ADD fired_colour TEXT;
+
+ALTER TABLE clays
+  ADD grog REAL;
+
+ALTER TABLE clays
+  ADD is_active BOOLEAN NOT NULL DEFAULT 1;
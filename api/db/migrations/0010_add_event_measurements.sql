@@ -0,0 +1,12 @@
+-- Measurements are in millimetres and grams.
+ALTER TABLE events
+  ADD height REAL;
+
+ALTER TABLE events
+  ADD width REAL;
+
+ALTER TABLE events
+  ADD weight REAL;
+
+ALTER TABLE events
+  ADD wall_thickness REAL;
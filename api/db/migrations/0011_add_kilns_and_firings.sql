@@ -0,0 +1,29 @@
+CREATE TABLE kilns (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE firings (
+    id INTEGER PRIMARY KEY,
+    kiln_id INTEGER NOT NULL,
+    firing_type TEXT NOT NULL CHECK (firing_type IN ('Bisque', 'Glaze')),
+    target_cone TEXT,
+    started_at TEXT,
+    ended_at TEXT,
+    notes TEXT,
+    is_completed BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    FOREIGN KEY (kiln_id) REFERENCES kilns (id)
+);
+
+CREATE TABLE firing_works (
+    firing_id INTEGER NOT NULL,
+    work_id INTEGER NOT NULL,
+    PRIMARY KEY (firing_id, work_id),
+    FOREIGN KEY (firing_id) REFERENCES firings (id) ON DELETE CASCADE,
+    FOREIGN KEY (work_id) REFERENCES works (id)
+);
+
+ALTER TABLE events
+  ADD firing_id INTEGER REFERENCES firings (id) ON DELETE SET NULL;
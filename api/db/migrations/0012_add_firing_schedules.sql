@@ -0,0 +1,23 @@
+CREATE TABLE firing_schedules (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT
+);
+
+-- Ramp rates are in degrees Celsius per hour, temperatures in degrees Celsius.
+CREATE TABLE schedule_segments (
+    id INTEGER PRIMARY KEY,
+    schedule_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    ramp_rate REAL NOT NULL CHECK (ramp_rate > 0),
+    target_temperature REAL NOT NULL,
+    hold_minutes INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (schedule_id, position),
+    FOREIGN KEY (schedule_id) REFERENCES firing_schedules (id) ON DELETE CASCADE
+);
+
+ALTER TABLE firings
+  ADD schedule_id INTEGER REFERENCES firing_schedules (id) ON DELETE SET NULL;
+
+ALTER TABLE events
+  ADD schedule_id INTEGER REFERENCES firing_schedules (id) ON DELETE SET NULL;
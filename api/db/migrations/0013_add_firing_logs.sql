@@ -0,0 +1,34 @@
+CREATE TABLE firing_logs (
+    id INTEGER PRIMARY KEY,
+    firing_id INTEGER REFERENCES firings (id) ON DELETE SET NULL,
+    name TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
+
+CREATE TABLE firing_log_zones (
+    log_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (log_id, position),
+    FOREIGN KEY (log_id) REFERENCES firing_logs (id) ON DELETE CASCADE
+);
+
+-- Temperatures are in degrees Celsius.
+CREATE TABLE firing_log_readings (
+    log_id INTEGER NOT NULL,
+    zone INTEGER NOT NULL,
+    recorded_at TEXT NOT NULL,
+    temperature REAL NOT NULL,
+    FOREIGN KEY (log_id) REFERENCES firing_logs (id) ON DELETE CASCADE
+);
+
+CREATE INDEX firing_log_readings_log ON firing_log_readings (log_id, recorded_at);
+
+-- The transitions out of a firing state that a log covers.
+CREATE TABLE firing_log_events (
+    log_id INTEGER NOT NULL,
+    event_id INTEGER NOT NULL,
+    PRIMARY KEY (log_id, event_id),
+    FOREIGN KEY (log_id) REFERENCES firing_logs (id) ON DELETE CASCADE,
+    FOREIGN KEY (event_id) REFERENCES events (id)
+);
@@ -0,0 +1,10 @@
+-- Space a work takes up in the kiln, in centimetres unlike the measurements
+-- taken on events, which are in millimetres.
+ALTER TABLE works
+  ADD footprint_width_cm REAL;
+
+ALTER TABLE works
+  ADD footprint_depth_cm REAL;
+
+ALTER TABLE works
+  ADD height_cm REAL;
@@ -0,0 +1,16 @@
+ALTER TABLE events
+  ADD kiln_id INTEGER REFERENCES kilns (id) ON DELETE SET NULL;
+
+UPDATE events
+SET kiln_id = (SELECT kiln_id FROM firings WHERE firings.id = events.firing_id)
+WHERE firing_id IS NOT NULL;
+
+CREATE TABLE kiln_maintenance (
+    id INTEGER PRIMARY KEY,
+    kiln_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('ElementReplacement', 'ThermocoupleChange', 'ShelfWash', 'RelaySwap')),
+    performed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    notes TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    FOREIGN KEY (kiln_id) REFERENCES kilns (id) ON DELETE CASCADE
+);
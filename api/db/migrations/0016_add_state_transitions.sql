@@ -0,0 +1,36 @@
+-- Key identifying each state in the API, alongside its display name.
+ALTER TABLE states
+  ADD key TEXT NOT NULL DEFAULT '';
+
+UPDATE states SET key = 'Thrown' WHERE id = 1;
+UPDATE states SET key = 'Trimming' WHERE id = 2;
+UPDATE states SET key = 'Handbuilt' WHERE id = 3;
+UPDATE states SET key = 'AwaitingBisqueFiring' WHERE id = 4;
+UPDATE states SET key = 'AwaitingGlazeFiring' WHERE id = 5;
+UPDATE states SET key = 'Finished' WHERE id = 6;
+UPDATE states SET key = 'Recycled' WHERE id = 7;
+
+CREATE UNIQUE INDEX states_key ON states (key);
+
+CREATE TABLE state_transitions (
+    from_state INTEGER NOT NULL,
+    to_state INTEGER NOT NULL,
+    PRIMARY KEY (from_state, to_state),
+    FOREIGN KEY (from_state) REFERENCES states (id),
+    FOREIGN KEY (to_state) REFERENCES states (id)
+);
+
+INSERT INTO state_transitions (from_state, to_state) VALUES
+    (1, 2),
+    (2, 4),
+    (3, 4),
+    (4, 5),
+    (5, 6),
+    (7, 1),
+    (7, 3),
+    (1, 7),
+    (2, 7),
+    (3, 7),
+    (4, 7),
+    (5, 7),
+    (7, 7);
@@ -0,0 +1,58 @@
+-- Profiles works are made under, each with its own allowed transitions.
+CREATE TABLE workflows (
+    id INTEGER PRIMARY KEY,
+    key TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    description TEXT
+);
+
+INSERT INTO workflows (id, key, name, description) VALUES
+    (1, 'Standard', 'Standard', 'Bisque fired, then glaze fired.'),
+    (2, 'SingleFire', 'Single fire', 'Raw glazed and fired once.'),
+    (3, 'Raku', 'Raku', 'Bisque fired, then raku fired, with refires.'),
+    (4, 'PitFire', 'Pit fire', 'Pit fired, with or without a bisque firing first.'),
+    (5, 'Refire', 'Refire', 'Glaze fired again after finishing to fix defects.');
+
+CREATE TABLE new_state_transitions (
+    workflow_id INTEGER NOT NULL,
+    from_state INTEGER NOT NULL,
+    to_state INTEGER NOT NULL,
+    PRIMARY KEY (workflow_id, from_state, to_state),
+    FOREIGN KEY (workflow_id) REFERENCES workflows (id),
+    FOREIGN KEY (from_state) REFERENCES states (id),
+    FOREIGN KEY (to_state) REFERENCES states (id)
+);
+
+INSERT INTO new_state_transitions (workflow_id, from_state, to_state)
+SELECT w.id, st.from_state, st.to_state
+FROM state_transitions st, workflows w
+WHERE w.key IN ('Standard', 'Raku', 'PitFire', 'Refire');
+
+DROP TABLE state_transitions;
+ALTER TABLE new_state_transitions RENAME TO state_transitions;
+
+-- Raku and refired works can go back into a glaze firing once finished.
+INSERT INTO state_transitions (workflow_id, from_state, to_state) VALUES
+    (3, 6, 5),
+    (5, 6, 5);
+
+-- Pit fired works may skip the bisque firing.
+INSERT INTO state_transitions (workflow_id, from_state, to_state) VALUES
+    (4, 2, 5),
+    (4, 3, 5);
+
+INSERT INTO state_transitions (workflow_id, from_state, to_state) VALUES
+    (2, 1, 2),
+    (2, 2, 5),
+    (2, 3, 5),
+    (2, 5, 6),
+    (2, 7, 1),
+    (2, 7, 3),
+    (2, 1, 7),
+    (2, 2, 7),
+    (2, 3, 7),
+    (2, 5, 7),
+    (2, 7, 7);
+
+ALTER TABLE works
+  ADD workflow_id INTEGER NOT NULL DEFAULT 1 REFERENCES workflows (id);
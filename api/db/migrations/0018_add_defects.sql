@@ -0,0 +1,23 @@
+INSERT INTO states (name, key) VALUES
+    ('Failed in firing', 'FailedInFiring');
+
+-- Works in any workflow can fail in either firing.
+INSERT INTO state_transitions (workflow_id, from_state, to_state)
+SELECT w.id, s.id, f.id
+FROM workflows w, states s, states f
+WHERE s.key IN ('AwaitingBisqueFiring', 'AwaitingGlazeFiring')
+AND f.key = 'FailedInFiring';
+
+-- Works that failed in a firing can then be recycled.
+INSERT INTO state_transitions (workflow_id, from_state, to_state)
+SELECT w.id, f.id, r.id
+FROM workflows w, states f, states r
+WHERE f.key = 'FailedInFiring'
+AND r.key = 'Recycled';
+
+-- Why a work was recycled or failed in a firing.
+ALTER TABLE events
+  ADD defect TEXT CHECK (defect IN ('SCrack', 'Warping', 'BlowOut', 'Crawling', 'Pinholing', 'Crazing', 'Shivering', 'CollapsedOnWheel'));
+
+ALTER TABLE events
+  ADD defect_note TEXT;
@@ -0,0 +1,11 @@
+ALTER TABLE events
+  ADD note TEXT;
+
+-- Photos taken of a work as it transitioned, in the order given.
+CREATE TABLE event_images (
+    event_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    image_key TEXT NOT NULL,
+    PRIMARY KEY (event_id, position),
+    FOREIGN KEY (event_id) REFERENCES events (id)
+);
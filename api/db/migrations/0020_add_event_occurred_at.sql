@@ -0,0 +1,36 @@
+-- When a transition happened, which may be before it was recorded.
+-- Transitions recorded without a time happened when they were recorded.
+-- Ids of deleted events, such as those undone, are never reused, so event
+-- streams resuming after the last id they sent miss nothing.
+CREATE TABLE new_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    work_id INTEGER NOT NULL,
+    previous_state INTEGER,
+    current_state INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    height REAL,
+    width REAL,
+    weight REAL,
+    wall_thickness REAL,
+    firing_id INTEGER REFERENCES firings (id) ON DELETE SET NULL,
+    schedule_id INTEGER REFERENCES firing_schedules (id) ON DELETE SET NULL,
+    kiln_id INTEGER REFERENCES kilns (id) ON DELETE SET NULL,
+    defect TEXT CHECK (defect IN ('SCrack', 'Warping', 'BlowOut', 'Crawling', 'Pinholing', 'Crazing', 'Shivering', 'CollapsedOnWheel')),
+    defect_note TEXT,
+    note TEXT,
+    occurred_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    FOREIGN KEY (work_id) REFERENCES works (id),
+    FOREIGN KEY (previous_state) REFERENCES states (id),
+    FOREIGN KEY (current_state) REFERENCES states (id)
+);
+
+INSERT INTO new_events (id, work_id, previous_state, current_state, created_at, height, width,
+    weight, wall_thickness, firing_id, schedule_id, kiln_id, defect, defect_note, note, occurred_at)
+SELECT id, work_id, previous_state, current_state, created_at, height, width,
+    weight, wall_thickness, firing_id, schedule_id, kiln_id, defect, defect_note, note, created_at
+FROM events;
+
+DROP TABLE events;
+ALTER TABLE new_events RENAME TO events;
+
+CREATE INDEX events_work_occurred_at ON events (work_id, occurred_at, id);
@@ -0,0 +1,9 @@
+-- Transitions that were undone, kept as they were returned by the API. Ids
+-- are never reused, as event streams resume after the last one they sent.
+CREATE TABLE event_reverts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    work_id INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    reverted_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    FOREIGN KEY (work_id) REFERENCES works (id)
+);
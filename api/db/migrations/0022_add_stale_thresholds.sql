@@ -0,0 +1,20 @@
+-- Days works may stay in a state before they are stale, if ever.
+ALTER TABLE states
+  ADD max_days REAL;
+
+UPDATE states SET max_days = 3 WHERE key = 'Thrown';
+UPDATE states SET max_days = 7 WHERE key = 'Trimming';
+UPDATE states SET max_days = 14 WHERE key = 'Handbuilt';
+UPDATE states SET max_days = 30 WHERE key = 'AwaitingBisqueFiring';
+UPDATE states SET max_days = 30 WHERE key = 'AwaitingGlazeFiring';
+
+-- Thresholds for works made of a particular clay, overriding those of the
+-- state.
+CREATE TABLE clay_state_thresholds (
+    clay_id INTEGER NOT NULL,
+    state_id INTEGER NOT NULL,
+    max_days REAL NOT NULL CHECK (max_days > 0),
+    PRIMARY KEY (clay_id, state_id),
+    FOREIGN KEY (clay_id) REFERENCES clays (id),
+    FOREIGN KEY (state_id) REFERENCES states (id)
+);
@@ -0,0 +1,15 @@
+-- Notifications raised by the reminder rules, one per channel. The key
+-- identifies what raised it, so a notification is never raised twice.
+CREATE TABLE notifications (
+    id INTEGER PRIMARY KEY,
+    key TEXT NOT NULL,
+    channel TEXT NOT NULL CHECK (channel IN ('Email', 'Webhook')),
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'Pending' CHECK (status IN ('Pending', 'Sent', 'Failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    sent_at TEXT,
+    UNIQUE (key, channel)
+);
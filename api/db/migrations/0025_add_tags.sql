@@ -0,0 +1,23 @@
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE work_tags (
+    work_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (work_id, tag_id),
+    FOREIGN KEY (work_id) REFERENCES works (id),
+    FOREIGN KEY (tag_id) REFERENCES tags (id)
+);
+
+CREATE TABLE project_tags (
+    project_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (project_id, tag_id),
+    FOREIGN KEY (project_id) REFERENCES projects (id),
+    FOREIGN KEY (tag_id) REFERENCES tags (id)
+);
+
+CREATE INDEX work_tags_tag_id ON work_tags (tag_id);
+CREATE INDEX project_tags_tag_id ON project_tags (tag_id);
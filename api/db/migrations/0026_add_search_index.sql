@@ -0,0 +1,19 @@
+-- Full text index over the text of works, projects and glazes, kept in step
+-- with them by the handlers that change them.
+CREATE VIRTUAL TABLE search_index USING fts5(
+    resource UNINDEXED,
+    resource_id UNINDEXED,
+    name,
+    description,
+    glaze_description,
+    tokenize = 'porter unicode61'
+);
+
+INSERT INTO search_index (resource, resource_id, name, description, glaze_description)
+SELECT 'Work', id, name, notes, glaze_description FROM works;
+
+INSERT INTO search_index (resource, resource_id, name, description, glaze_description)
+SELECT 'Project', id, name, description, NULL FROM projects;
+
+INSERT INTO search_index (resource, resource_id, name, description, glaze_description)
+SELECT 'Glaze', id, name, description, NULL FROM glazes;
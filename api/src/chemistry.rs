@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use crate::models::{Oxide, OxideAnalysis, Umf};
+
+#[derive(PartialEq, Debug)]
+pub(crate) enum OxideGroup {
+    /// Alkali fluxes.
+    R2O,
+    /// Alkaline earth fluxes.
+    RO,
+    /// Stabilisers.
+    R2O3,
+    /// Glass formers.
+    RO2,
+}
+
+impl Oxide {
+    pub(crate) fn molecular_weight(&self) -> f64 {
+        match self {
+            Oxide::SiO2 => 60.08,
+            Oxide::TiO2 => 79.87,
+            Oxide::Al2O3 => 101.96,
+            Oxide::B2O3 => 69.62,
+            Oxide::Fe2O3 => 159.69,
+            Oxide::Li2O => 29.88,
+            Oxide::Na2O => 61.98,
+            Oxide::K2O => 94.2,
+            Oxide::MgO => 40.3,
+            Oxide::CaO => 56.08,
+            Oxide::SrO => 103.62,
+            Oxide::BaO => 153.33,
+            Oxide::ZnO => 81.38,
+        }
+    }
+
+    /// Appen factor for the contribution of one mole percent of this oxide
+    /// to a glass's thermal expansion, in 10^-7 per degree Celsius.
+    pub(crate) fn expansion_factor(&self) -> f64 {
+        match self {
+            Oxide::SiO2 => 0.38,
+            Oxide::TiO2 => -0.15,
+            Oxide::Al2O3 => -0.3,
+            Oxide::B2O3 => 0.0,
+            Oxide::Fe2O3 => 0.55,
+            Oxide::Li2O => 2.7,
+            Oxide::Na2O => 3.95,
+            Oxide::K2O => 4.65,
+            Oxide::MgO => 0.6,
+            Oxide::CaO => 1.3,
+            Oxide::SrO => 1.6,
+            Oxide::BaO => 2.0,
+            Oxide::ZnO => 0.5,
+        }
+    }
+
+    pub(crate) fn group(&self) -> OxideGroup {
+        match self {
+            Oxide::Li2O | Oxide::Na2O | Oxide::K2O => OxideGroup::R2O,
+            Oxide::MgO | Oxide::CaO | Oxide::SrO | Oxide::BaO | Oxide::ZnO => OxideGroup::RO,
+            Oxide::Al2O3 | Oxide::B2O3 | Oxide::Fe2O3 => OxideGroup::R2O3,
+            Oxide::SiO2 | Oxide::TiO2 => OxideGroup::RO2,
+        }
+    }
+
+    pub(crate) fn is_flux(&self) -> bool {
+        matches!(self.group(), OxideGroup::R2O | OxideGroup::RO)
+    }
+}
+
+/// Calculates the unity molecular formula of a recipe, given as pairs of
+/// each material's percentage in the recipe and its oxide analysis. Returns
+/// `None` if the recipe contains no fluxes to unify against.
+pub(crate) fn unity_formula(recipe: &[(f64, &OxideAnalysis)]) -> Option<Umf> {
+    let mut moles: BTreeMap<Oxide, f64> = BTreeMap::new();
+    for (percentage, analysis) in recipe {
+        for (oxide, oxide_percentage) in analysis.iter() {
+            *moles.entry(*oxide).or_default() +=
+                percentage * oxide_percentage / 100.0 / oxide.molecular_weight();
+        }
+    }
+
+    let flux: f64 = moles
+        .iter()
+        .filter(|(oxide, _)| oxide.is_flux())
+        .map(|(_, m)| m)
+        .sum();
+    if flux <= 0.0 {
+        return None;
+    }
+
+    let oxides = moles
+        .into_iter()
+        .map(|(oxide, m)| (oxide, m / flux))
+        .collect::<BTreeMap<Oxide, f64>>();
+
+    let total = |group: OxideGroup| {
+        oxides
+            .iter()
+            .filter(|(oxide, _)| oxide.group() == group)
+            .map(|(_, m)| m)
+            .sum()
+    };
+
+    let si_al_ratio = match (oxides.get(&Oxide::SiO2), oxides.get(&Oxide::Al2O3)) {
+        (Some(si), Some(al)) if *al > 0.0 => Some(si / al),
+        _ => None,
+    };
+
+    Some(Umf {
+        r2o: total(OxideGroup::R2O),
+        ro: total(OxideGroup::RO),
+        si_al_ratio,
+        oxides,
+    })
+}
+
+/// Estimates the coefficient of thermal expansion of a glaze from its oxide
+/// analysis by weight, in 10^-7 per degree Celsius.
+pub(crate) fn estimated_coe(analysis: &OxideAnalysis) -> Option<f64> {
+    let moles = analysis
+        .iter()
+        .map(|(oxide, percentage)| (oxide, percentage / oxide.molecular_weight()))
+        .collect::<Vec<(&Oxide, f64)>>();
+
+    let total: f64 = moles.iter().map(|(_, m)| m).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    Some(
+        moles
+            .iter()
+            .map(|(oxide, m)| oxide.expansion_factor() * 100.0 * m / total)
+            .sum(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analysis(oxides: &[(Oxide, f64)]) -> OxideAnalysis {
+        oxides.iter().cloned().collect()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_unity_formula() {
+        let whiting = analysis(&[(Oxide::CaO, 100.0)]);
+        let soda = analysis(&[(Oxide::Na2O, 100.0)]);
+        let silica = analysis(&[(Oxide::SiO2, 100.0)]);
+        let alumina = analysis(&[(Oxide::Al2O3, 100.0)]);
+
+        // 0.1 moles of each flux, 0.6 moles of silica and 0.06 of alumina.
+        let umf = unity_formula(&[
+            (5.608, &whiting),
+            (6.198, &soda),
+            (36.048, &silica),
+            (6.1176, &alumina),
+        ])
+        .unwrap();
+
+        assert_close(umf.oxides[&Oxide::CaO], 0.5);
+        assert_close(umf.oxides[&Oxide::Na2O], 0.5);
+        assert_close(umf.oxides[&Oxide::SiO2], 3.0);
+        assert_close(umf.oxides[&Oxide::Al2O3], 0.3);
+        assert_close(umf.r2o, 0.5);
+        assert_close(umf.ro, 0.5);
+        assert_close(umf.si_al_ratio.unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_unity_formula_without_flux() {
+        let silica = analysis(&[(Oxide::SiO2, 100.0)]);
+        assert!(unity_formula(&[(100.0, &silica)]).is_none());
+    }
+
+    #[test]
+    fn test_estimated_coe() {
+        // Equal moles of silica and soda.
+        let analysis = analysis(&[(Oxide::SiO2, 60.08), (Oxide::Na2O, 61.98)]);
+        assert_close(estimated_coe(&analysis).unwrap(), 50.0 * 0.38 + 50.0 * 3.95);
+
+        assert!(estimated_coe(&OxideAnalysis::new()).is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 use std::fs::read_to_string;
+use std::num::NonZeroU64;
 use std::path::Path;
 
 #[derive(Clone, Deserialize)]
@@ -14,11 +15,98 @@ pub struct AuthConfig {
     pub jwt_secret: String,
 }
 
+/// Dimensions of the kiln used to plan loads, in centimetres.
+#[derive(Clone, Deserialize)]
+pub struct KilnConfig {
+    pub shelf_width: f64,
+    pub shelf_depth: f64,
+    pub shelf_thickness: f64,
+    pub interior_height: f64,
+    /// Heights of the posts available to hold up each shelf.
+    pub post_heights: Vec<f64>,
+    /// Clearance left around and above each work.
+    #[serde(default)]
+    pub spacing: f64,
+}
+
+/// Number of firings after which each kind of kiln maintenance is due.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    pub element_replacement: usize,
+    pub thermocouple_change: usize,
+    pub shelf_wash: usize,
+    pub relay_swap: usize,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        MaintenanceConfig {
+            element_replacement: 150,
+            thermocouple_change: 100,
+            shelf_wash: 20,
+            relay_swap: 200,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Whether to connect with TLS, which a local mail catcher won't expect.
+    #[serde(default)]
+    pub tls: bool,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+}
+
+#[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+/// Rules for reminding the studio of works needing attention, and the
+/// channels reminders are delivered through.
+#[derive(Clone, Deserialize)]
+pub struct NotificationConfig {
+    /// Seconds between each evaluation of the rules.
+    #[serde(default = "NotificationConfig::default_interval")]
+    pub interval: NonZeroU64,
+    /// Remind of works left in a state for longer than its threshold.
+    #[serde(default)]
+    pub stale_works: bool,
+    /// Send a digest of the transitions made over the previous day or week.
+    pub digest: Option<DigestFrequency>,
+    pub smtp: Option<SmtpConfig>,
+    pub webhook: Option<WebhookConfig>,
+}
+
+impl NotificationConfig {
+    fn default_interval() -> NonZeroU64 {
+        NonZeroU64::new(300).unwrap()
+    }
+}
+
 #[derive(Clone, Deserialize)]
 pub struct Config {
     pub s3: S3Config,
     pub auth: AuthConfig,
     pub db: String,
+    pub kiln: Option<KilnConfig>,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    /// Reminders are only raised when this is set; see the README for an
+    /// example.
+    pub notifications: Option<NotificationConfig>,
 }
 
 impl Config {
@@ -14,6 +14,45 @@ pub(crate) enum Error {
     InvalidPassword,
     NotLoggedIn,
     InvalidJWT,
+    InvalidRecipe { base_total: f64 },
+    InvalidPercentage(String),
+    InvalidBatchSize,
+    InvalidCoats,
+    UnknownMaterial(String),
+    UnfluxedRecipe,
+    UnknownExpansion,
+    ClayInUse,
+    InvalidShrinkage,
+    InvalidCone(String),
+    InvalidConeRange,
+    InvalidExpansion,
+    InvalidGrog,
+    GlazeInUse,
+    KilnInUse,
+    FiringCompleted,
+    NotAwaitingFiring,
+    NotLeavingFiring,
+    InvalidFiringLog(usize),
+    InvalidSegment(usize),
+    KilnNotConfigured,
+    UnknownState(String),
+    StateExists,
+    StateInUse,
+    BuiltInState,
+    InvalidMaxDays,
+    UnknownWorkflow(String),
+    WorkflowExists,
+    WorkflowInUse,
+    DefaultWorkflow,
+    NotFailing,
+    NotInFiring(i32),
+    AlreadyLoaded(i32),
+    InvalidOccurredAt,
+    InitialEvent,
+    FiringEvent,
+    InvalidWebhookUrl,
+    InvalidWebhookSecret,
+    DeliveryNotFailed,
 }
 
 impl From<sqlx::Error> for Error {
@@ -32,13 +71,19 @@ impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
         let internal_error = (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "an internal server error has occured",
+            "an internal server error has occured".to_string(),
         );
         let (status, msg) = match self {
             Self::InternalServer => internal_error,
-            Self::ResourceNotFound => (StatusCode::NOT_FOUND, "resource not found"),
-            Self::ImageUpload => (StatusCode::INTERNAL_SERVER_ERROR, "failed to upload image"),
-            Self::InvalidStateTransition => (StatusCode::BAD_REQUEST, "invalid state transition"),
+            Self::ResourceNotFound => (StatusCode::NOT_FOUND, "resource not found".to_string()),
+            Self::ImageUpload => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to upload image".to_string(),
+            ),
+            Self::InvalidStateTransition => (
+                StatusCode::BAD_REQUEST,
+                "invalid state transition".to_string(),
+            ),
             Self::Sqlx(e) => {
                 event!(Level::ERROR, source = "Sqlx", err = ?e);
                 internal_error
@@ -49,19 +94,184 @@ impl IntoResponse for Error {
             }
             Self::InvalidPassword => {
                 event!(Level::WARN, source = "Authentication: Invalid password");
-                (StatusCode::BAD_REQUEST, "invalid password")
+                (StatusCode::BAD_REQUEST, "invalid password".to_string())
             }
             Self::NotLoggedIn => {
                 event!(
                     Level::WARN,
                     source = "Authentication: Request made while not logged in"
                 );
-                (StatusCode::UNAUTHORIZED, "yous are not logged in mate")
+                (
+                    StatusCode::UNAUTHORIZED,
+                    "yous are not logged in mate".to_string(),
+                )
             }
             Self::InvalidJWT => {
                 event!(Level::WARN, source = "Authentication: Invalid JWT token");
-                (StatusCode::UNAUTHORIZED, "invalid token")
+                (StatusCode::UNAUTHORIZED, "invalid token".to_string())
             }
+            Self::InvalidRecipe { base_total } => (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "recipe base ingredients must sum to 100%, got {}%",
+                    base_total
+                ),
+            ),
+            Self::InvalidPercentage(material) => (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "percentage of {} must be a finite number of at least 0",
+                    material
+                ),
+            ),
+            Self::InvalidBatchSize => (
+                StatusCode::BAD_REQUEST,
+                "batch size must be greater than zero".to_string(),
+            ),
+            Self::InvalidCoats => (
+                StatusCode::BAD_REQUEST,
+                "a glaze layer must have at least one coat".to_string(),
+            ),
+            Self::UnknownMaterial(name) => (
+                StatusCode::BAD_REQUEST,
+                format!("unknown material: {}", name),
+            ),
+            Self::UnfluxedRecipe => (
+                StatusCode::BAD_REQUEST,
+                "recipe contains no fluxes".to_string(),
+            ),
+            Self::UnknownExpansion => (
+                StatusCode::BAD_REQUEST,
+                "clay has no known coefficient of thermal expansion".to_string(),
+            ),
+            Self::ClayInUse => (
+                StatusCode::CONFLICT,
+                "clay is still used by one or more works".to_string(),
+            ),
+            Self::InvalidShrinkage => (
+                StatusCode::BAD_REQUEST,
+                "shrinkage must be a fraction of at least 0 and less than 1".to_string(),
+            ),
+            Self::InvalidCone(cone) => (StatusCode::BAD_REQUEST, format!("unknown cone: {}", cone)),
+            Self::InvalidConeRange => (
+                StatusCode::BAD_REQUEST,
+                "cone_min must not be hotter than cone_max".to_string(),
+            ),
+            Self::InvalidExpansion => (
+                StatusCode::BAD_REQUEST,
+                "coe must be a non-negative number".to_string(),
+            ),
+            Self::InvalidGrog => (
+                StatusCode::BAD_REQUEST,
+                "grog must be a non-negative number".to_string(),
+            ),
+            Self::GlazeInUse => (
+                StatusCode::CONFLICT,
+                "glaze is still used by one or more works".to_string(),
+            ),
+            Self::KilnInUse => (
+                StatusCode::CONFLICT,
+                "kiln is still used by one or more firings".to_string(),
+            ),
+            Self::FiringCompleted => (
+                StatusCode::CONFLICT,
+                "firing has already been completed".to_string(),
+            ),
+            Self::NotAwaitingFiring => (
+                StatusCode::BAD_REQUEST,
+                "work is not awaiting this type of firing".to_string(),
+            ),
+            Self::NotLeavingFiring => (
+                StatusCode::BAD_REQUEST,
+                "a kiln or firing schedule can only be given when leaving a firing state"
+                    .to_string(),
+            ),
+            Self::InvalidFiringLog(0) => (
+                StatusCode::BAD_REQUEST,
+                "firing log contains no readings".to_string(),
+            ),
+            Self::InvalidSegment(position) => (
+                StatusCode::BAD_REQUEST,
+                format!("segment {} must ramp faster than 0 degrees an hour to a finite temperature and hold for 0 minutes or more", position),
+            ),
+            Self::InvalidFiringLog(line) => (
+                StatusCode::BAD_REQUEST,
+                format!("could not parse firing log at line {}", line),
+            ),
+            Self::KilnNotConfigured => (
+                StatusCode::NOT_IMPLEMENTED,
+                "no kiln dimensions have been configured".to_string(),
+            ),
+            Self::UnknownState(key) => (StatusCode::BAD_REQUEST, format!("unknown state: {}", key)),
+            Self::StateExists => (
+                StatusCode::CONFLICT,
+                "a state with this key already exists".to_string(),
+            ),
+            Self::StateInUse => (
+                StatusCode::CONFLICT,
+                "state is still referenced by one or more events".to_string(),
+            ),
+            Self::BuiltInState => (
+                StatusCode::CONFLICT,
+                "built in states cannot be renamed or removed".to_string(),
+            ),
+            Self::InvalidMaxDays => (
+                StatusCode::BAD_REQUEST,
+                "max_days must be a positive number of days".to_string(),
+            ),
+            Self::UnknownWorkflow(key) => (
+                StatusCode::BAD_REQUEST,
+                format!("unknown workflow: {}", key),
+            ),
+            Self::WorkflowExists => (
+                StatusCode::CONFLICT,
+                "a workflow with this key already exists".to_string(),
+            ),
+            Self::WorkflowInUse => (
+                StatusCode::CONFLICT,
+                "workflow is still followed by one or more works".to_string(),
+            ),
+            Self::DefaultWorkflow => (
+                StatusCode::CONFLICT,
+                "the default workflow cannot be renamed or removed".to_string(),
+            ),
+            Self::NotFailing => (
+                StatusCode::BAD_REQUEST,
+                "a defect can only be given when recycling a work or failing it in a firing"
+                    .to_string(),
+            ),
+            Self::NotInFiring(work_id) => (
+                StatusCode::BAD_REQUEST,
+                format!("work {} is not loaded into this firing", work_id),
+            ),
+            Self::AlreadyLoaded(work_id) => (
+                StatusCode::CONFLICT,
+                format!("work {} is already loaded into another firing", work_id),
+            ),
+            Self::InvalidOccurredAt => (
+                StatusCode::BAD_REQUEST,
+                "a transition cannot occur before the previous one or in the future".to_string(),
+            ),
+            Self::InitialEvent => (
+                StatusCode::CONFLICT,
+                "the event a work was created with cannot be undone".to_string(),
+            ),
+            Self::FiringEvent => (
+                StatusCode::CONFLICT,
+                "events recorded by completing a firing cannot be undone".to_string(),
+            ),
+            Self::InvalidWebhookUrl => (
+                StatusCode::BAD_REQUEST,
+                "a webhook must be an http or https url".to_string(),
+            ),
+            Self::InvalidWebhookSecret => (
+                StatusCode::BAD_REQUEST,
+                "webhook secret must not be blank".to_string(),
+            ),
+            Self::DeliveryNotFailed => (
+                StatusCode::CONFLICT,
+                "only failed deliveries can be retried".to_string(),
+            ),
         };
         (status, Json(json!({ "error": msg }))).into_response()
     }
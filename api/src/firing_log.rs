@@ -0,0 +1,416 @@
+use chrono::NaiveDateTime;
+
+use crate::models::{FiringLogStatistics, LogSegment, TemperaturePoint, Trend};
+
+/// Temperatures above which time is totalled in a log's statistics.
+pub(crate) const HIGH_TEMPERATURE: f64 = 1000.0;
+
+/// Rates of change, in degrees per hour, within which the kiln is considered
+/// to be holding.
+const HOLD_RATE: f64 = 15.0;
+
+/// Segments shorter than this are treated as noise and folded into the
+/// segment before them.
+const MIN_SEGMENT_MINUTES: f64 = 10.0;
+
+static TIMESTAMP_FORMATS: [&str; 7] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+];
+
+pub(crate) struct Reading {
+    pub(crate) recorded_at: NaiveDateTime,
+    pub(crate) temperatures: Vec<f64>,
+}
+
+pub(crate) struct ParsedLog {
+    pub(crate) zones: Vec<String>,
+    pub(crate) readings: Vec<Reading>,
+}
+
+/// Formats a log's timestamps may be written in, where None stands for Unix
+/// timestamps in seconds.
+fn timestamp_formats() -> impl Iterator<Item = Option<&'static str>> {
+    TIMESTAMP_FORMATS.iter().copied().map(Some).chain([None])
+}
+
+fn parse_timestamp(value: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+    match format {
+        Some(format) => NaiveDateTime::parse_from_str(value, format).ok(),
+        None => value
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| NaiveDateTime::from_timestamp_opt(secs, 0)),
+    }
+}
+
+/// Picks the one format every timestamp of a log is written in, given each
+/// timestamp with its line. Errors with the first line no single format can
+/// read, or with the first line if days and months could be either way
+/// round, since reading some rows one way and some the other would jumble
+/// the readings.
+fn timestamp_format(timestamps: &[(usize, &str)]) -> Result<Option<&'static str>, usize> {
+    let mut fitting = Vec::new();
+    let mut furthest_line = 0;
+    for format in timestamp_formats() {
+        match timestamps
+            .iter()
+            .find(|(_, value)| parse_timestamp(value, format).is_none())
+        {
+            Some((line, _)) => furthest_line = furthest_line.max(*line),
+            None => fitting.push(format),
+        }
+    }
+
+    let is_fitting = |prefix: &str| {
+        fitting
+            .iter()
+            .any(|format| format.is_some_and(|f| f.starts_with(prefix)))
+    };
+    let first_line = timestamps.first().map_or(0, |(line, _)| *line);
+    match fitting.first() {
+        Some(_) if is_fitting("%d/%m") && is_fitting("%m/%d") => Err(first_line),
+        Some(format) => Ok(*format),
+        None => Err(furthest_line),
+    }
+}
+
+/// Parses a controller's CSV export, made of a timestamp column followed by
+/// one temperature column per zone, with an optional header row naming them.
+/// Errors with the line that could not be parsed, or 0 if there were no
+/// readings at all.
+pub(crate) fn parse_csv(csv: &str) -> Result<ParsedLog, usize> {
+    let rows = csv
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let fields = line
+                .split([',', ';'])
+                .map(|f| f.trim().trim_matches('"'))
+                .collect::<Vec<&str>>();
+            (index + 1, fields)
+        })
+        .collect::<Vec<(usize, Vec<&str>)>>();
+
+    let mut zones: Vec<String> = Vec::new();
+    let mut rows = rows.as_slice();
+    if let Some((_, fields)) = rows.first() {
+        if timestamp_formats().all(|format| parse_timestamp(fields[0], format).is_none()) {
+            zones = fields[1..].iter().map(|f| f.to_string()).collect();
+            rows = &rows[1..];
+        }
+    }
+
+    if rows.is_empty() {
+        return Err(0);
+    }
+
+    let timestamps = rows
+        .iter()
+        .map(|(line, fields)| (*line, fields[0]))
+        .collect::<Vec<(usize, &str)>>();
+    let format = timestamp_format(&timestamps)?;
+
+    let mut readings: Vec<Reading> = Vec::new();
+    for (line, fields) in rows {
+        let recorded_at = parse_timestamp(fields[0], format).ok_or(*line)?;
+
+        // NaN and infinite readings parse, but can't be stored or measured.
+        let temperatures = fields[1..]
+            .iter()
+            .map(|f| f.parse::<f64>().ok().filter(|t| t.is_finite()))
+            .collect::<Option<Vec<f64>>>()
+            .ok_or(*line)?;
+
+        if zones.is_empty() {
+            zones = (1..=temperatures.len())
+                .map(|zone| format!("Zone {}", zone))
+                .collect();
+        }
+        if temperatures.is_empty() || temperatures.len() != zones.len() {
+            return Err(*line);
+        }
+
+        readings.push(Reading {
+            recorded_at,
+            temperatures,
+        });
+    }
+
+    readings.sort_by_key(|r| r.recorded_at);
+    Ok(ParsedLog { zones, readings })
+}
+
+fn hours_between(from: NaiveDateTime, to: NaiveDateTime) -> f64 {
+    (to - from).num_seconds() as f64 / 3600.0
+}
+
+/// Thins a series down to at most `max_points`, keeping its first and last
+/// points.
+pub(crate) fn downsample(points: &[TemperaturePoint], max_points: usize) -> Vec<TemperaturePoint> {
+    if points.len() <= max_points || max_points < 2 {
+        return points.to_vec();
+    }
+
+    let step = (points.len() - 1) as f64 / (max_points - 1) as f64;
+    (0..max_points)
+        .map(|i| points[(i as f64 * step).round() as usize].clone())
+        .collect()
+}
+
+fn trend(rate: f64) -> Trend {
+    if rate > HOLD_RATE {
+        Trend::Rising
+    } else if rate < -HOLD_RATE {
+        Trend::Falling
+    } else {
+        Trend::Holding
+    }
+}
+
+fn segment(points: &[TemperaturePoint], trend: Trend) -> LogSegment {
+    let (first, last) = (&points[0], &points[points.len() - 1]);
+    let hours = hours_between(first.time, last.time);
+
+    LogSegment {
+        trend,
+        started_at: first.time,
+        ended_at: last.time,
+        start_temperature: first.temperature,
+        end_temperature: last.temperature,
+        rate: if hours > 0.0 {
+            (last.temperature - first.temperature) / hours
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Splits a series into rising, holding and falling segments.
+fn segments(points: &[TemperaturePoint]) -> Vec<LogSegment> {
+    // Each range of points shares its first point with the end of the
+    // previous range.
+    let mut ranges: Vec<(usize, usize, Trend)> = Vec::new();
+    for i in 1..points.len() {
+        let rate = (points[i].temperature - points[i - 1].temperature)
+            / hours_between(points[i - 1].time, points[i].time).max(f64::EPSILON);
+        let current = trend(rate);
+
+        match ranges.last_mut() {
+            Some((_, end, t)) if *t == current => *end = i,
+            Some((start, end, t))
+                if hours_between(points[*start].time, points[*end].time) * 60.0
+                    < MIN_SEGMENT_MINUTES =>
+            {
+                *end = i;
+                *t = current;
+            }
+            _ => ranges.push((i - 1, i, current)),
+        }
+
+        // A short range relabelled with the trend after it continues the
+        // range before it, if that has the same trend.
+        if let [.., (_, previous_end, previous), (_, end, t)] = ranges.as_mut_slice() {
+            if previous == t {
+                *previous_end = *end;
+                ranges.pop();
+            }
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end, t)| segment(&points[start..=end], t))
+        .collect()
+}
+
+pub(crate) fn statistics(points: &[TemperaturePoint]) -> Option<FiringLogStatistics> {
+    let peak_temperature = points
+        .iter()
+        .map(|p| p.temperature)
+        .max_by(f64::total_cmp)?;
+    // Cooling is measured from the end of any hold at the peak.
+    let peak = points.iter().find(|p| p.temperature == peak_temperature)?;
+    let cooling_from = points.iter().rfind(|p| p.temperature == peak_temperature)?;
+    let last = &points[points.len() - 1];
+
+    let minutes_above = points
+        .windows(2)
+        .filter(|w| w[0].temperature >= HIGH_TEMPERATURE && w[1].temperature >= HIGH_TEMPERATURE)
+        .map(|w| hours_between(w[0].time, w[1].time) * 60.0)
+        .sum();
+
+    let cooling_hours = hours_between(cooling_from.time, last.time);
+    let cooling_rate = if cooling_hours > 0.0 {
+        Some((peak_temperature - last.temperature) / cooling_hours)
+    } else {
+        None
+    };
+
+    Some(FiringLogStatistics {
+        peak_temperature,
+        peak_at: peak.time,
+        minutes_above_1000: minutes_above,
+        cooling_rate,
+        segments: segments(points),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn series(start: NaiveDateTime, temperatures: &[f64]) -> Vec<TemperaturePoint> {
+        series_every(start, 30, temperatures)
+    }
+
+    fn series_every(
+        start: NaiveDateTime,
+        minutes: i64,
+        temperatures: &[f64],
+    ) -> Vec<TemperaturePoint> {
+        temperatures
+            .iter()
+            .enumerate()
+            .map(|(i, t)| TemperaturePoint {
+                time: start + Duration::minutes(minutes * i as i64),
+                temperature: *t,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let log = parse_csv(
+            "Time,Top,Bottom\n\
+             2023-05-01 08:00:00,20,21\n\
+             2023-05-01 08:01:00,22.5,23\n\n",
+        )
+        .unwrap();
+        assert_eq!(log.zones, vec!["Top", "Bottom"]);
+        assert_eq!(log.readings.len(), 2);
+        assert_eq!(log.readings[1].temperatures, vec![22.5, 23.0]);
+
+        let log = parse_csv("2023-05-01T08:00:00,20\n").unwrap();
+        assert_eq!(log.zones, vec!["Zone 1"]);
+
+        assert_eq!(
+            parse_csv("Time,Temp\n2023-05-01 08:00:00,hot\n").err(),
+            Some(2)
+        );
+        assert_eq!(parse_csv("Time,Temp\n").err(), Some(0));
+
+        // Readings that can't be stored or measured.
+        for reading in ["NaN", "inf", "-inf"] {
+            assert_eq!(
+                parse_csv(&format!(
+                    "Time,Temp\n2023-05-01 08:00:00,20\n2023-05-01 08:01:00,{}\n",
+                    reading
+                ))
+                .err(),
+                Some(3)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_date_order() {
+        // A US export: days of 12 or less would also read as day/month, so
+        // the later rows decide the format for the whole log.
+        let log = parse_csv(
+            "Time,Temp\n\
+             05/12/2023 23:30,1100\n\
+             05/13/2023 00:30,1220\n",
+        )
+        .unwrap();
+        assert_eq!(
+            log.readings
+                .iter()
+                .map(|r| r.recorded_at.format("%Y-%m-%d %H:%M").to_string())
+                .collect::<Vec<String>>(),
+            vec!["2023-05-12 23:30", "2023-05-13 00:30"]
+        );
+
+        // Could be either the 1st to 2nd of May or the 5th of January to the
+        // 5th of February.
+        assert_eq!(
+            parse_csv("Time,Temp\n01/05/2023 08:00,20\n02/05/2023 08:00,21\n").err(),
+            Some(2)
+        );
+
+        // Rows written in different formats can't be read as one log.
+        assert_eq!(
+            parse_csv("13/05/2023 08:00,20\n05/14/2023 08:00,21\n").err(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_downsample() {
+        let start = NaiveDateTime::default();
+        let points = series(start, &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+
+        let thinned = downsample(&points, 5);
+        assert_eq!(
+            thinned.iter().map(|p| p.temperature).collect::<Vec<f64>>(),
+            vec![0.0, 2.0, 4.0, 6.0, 8.0]
+        );
+        assert_eq!(downsample(&points, 100).len(), 9);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let start = NaiveDateTime::default();
+        // Ramp at 200C/h, hold at peak for an hour, then cool at 100C/h.
+        let points = series(
+            start,
+            &[
+                800.0, 900.0, 1000.0, 1100.0, 1100.0, 1100.0, 1050.0, 1000.0, 950.0,
+            ],
+        );
+
+        let stats = statistics(&points).unwrap();
+        assert_eq!(stats.peak_temperature, 1100.0);
+        assert_eq!(stats.peak_at, start + Duration::minutes(90));
+        assert_eq!(stats.minutes_above_1000, 150.0);
+        assert_eq!(stats.cooling_rate, Some(100.0));
+
+        let trends = stats
+            .segments
+            .iter()
+            .map(|s| (s.trend, s.rate))
+            .collect::<Vec<(Trend, f64)>>();
+        assert_eq!(
+            trends,
+            vec![
+                (Trend::Rising, 200.0),
+                (Trend::Holding, 0.0),
+                (Trend::Falling, -100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_segments_with_blip() {
+        // A ramp at 300C/h logged every minute, with one flat reading.
+        let mut temperatures = (0..16)
+            .map(|i| 100.0 + 5.0 * i as f64)
+            .collect::<Vec<f64>>();
+        temperatures.push(175.0);
+        temperatures.extend((1..=5).map(|i| 175.0 + 5.0 * i as f64));
+        let points = series_every(NaiveDateTime::default(), 1, &temperatures);
+
+        let segments = segments(&points);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].trend, Trend::Rising);
+        assert_eq!(segments[0].start_temperature, 100.0);
+        assert_eq!(segments[0].end_temperature, 200.0);
+    }
+}
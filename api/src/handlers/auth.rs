@@ -20,7 +20,7 @@ pub(crate) async fn login(
     let is_valid = match PasswordHash::new(&state.config.auth.hash) {
         Ok(parsed_hash) => Argon2::default()
             .verify_password(body.password.as_bytes(), &parsed_hash)
-            .map_or(false, |_| true),
+            .is_ok(),
         Err(_) => false,
     };
 
@@ -1,15 +1,36 @@
-use axum::extract::State;
+use axum::extract::{Json as ExtractJson, Path, Query, State};
+use serde::Deserialize;
+use std::collections::BTreeMap;
 
-use crate::models::Clay;
-use crate::result::JsonResult;
+use crate::error::Error;
+use crate::handlers::event::{EventDTO, EVENT_DTO_QUERY};
+use crate::handlers::glaze::fetch_glazes;
+use crate::models::{
+    cone_rank, fired_size, fired_volume, glaze_fit, is_valid_max_days, wet_size, wet_volume,
+    work_shrinkage, ApiResource, Clay, ClayShrinkage, Dimensions, Event, Glaze, GlazeFit,
+    LengthUnit, PutClay, ShrinkageCalculation, StateThreshold, VolumeUnit,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
 use crate::AppState;
 
+static CLAY_DTO_QUERY: &str = "
+SELECT id, name, description, shrinkage, coe, supplier, cone_min, cone_max, fired_colour, grog, is_active
+FROM clays
+";
+
 #[derive(sqlx::FromRow)]
 struct ClayDTO {
     id: i32,
     name: String,
     description: Option<String>,
     shrinkage: f64,
+    coe: Option<f64>,
+    supplier: Option<String>,
+    cone_min: Option<String>,
+    cone_max: Option<String>,
+    fired_colour: Option<String>,
+    grog: Option<f64>,
+    is_active: bool,
 }
 
 impl From<ClayDTO> for Clay {
@@ -19,17 +40,402 @@ impl From<ClayDTO> for Clay {
             name: clay.name,
             description: clay.description,
             shrinkage: clay.shrinkage,
+            coe: clay.coe,
+            supplier: clay.supplier,
+            cone_min: clay.cone_min,
+            cone_max: clay.cone_max,
+            fired_colour: clay.fired_colour,
+            grog: clay.grog,
+            is_active: clay.is_active,
         }
     }
 }
 
 pub(crate) async fn clays(State(appstate): State<AppState>) -> JsonResult<Vec<Clay>> {
-    sqlx::query_as::<_, ClayDTO>(
-        "SELECT id, name, description, shrinkage
-        FROM clays",
+    sqlx::query_as::<_, ClayDTO>(CLAY_DTO_QUERY)
+        .fetch_all(&appstate.pool)
+        .await
+        .map(|clays| clays.into_iter().map(Clay::from).collect::<Vec<Clay>>())
+        .into()
+}
+
+pub(crate) async fn clay(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Clay> {
+    sqlx::query_as::<_, ClayDTO>(&format!("{} {}", CLAY_DTO_QUERY, "WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await
+        .map(|opt_clay| opt_clay.map(Clay::from))
+        .into()
+}
+
+async fn fetch_compatible_glazes(appstate: &AppState, id: i32) -> Result<Vec<Glaze>, Error> {
+    let clay_coe = sqlx::query_scalar::<_, Option<f64>>("SELECT coe FROM clays WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await?
+        .ok_or(Error::ResourceNotFound)?
+        .ok_or(Error::UnknownExpansion)?;
+
+    Ok(fetch_glazes(appstate)
+        .await?
+        .into_iter()
+        .filter(|glaze| {
+            glaze
+                .coe
+                .is_some_and(|glaze_coe| glaze_fit(clay_coe, glaze_coe) == GlazeFit::Ok)
+        })
+        .collect::<Vec<Glaze>>())
+}
+
+pub(crate) async fn compatible_glazes(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<Glaze>> {
+    JsonResult(fetch_compatible_glazes(&appstate, id).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShrinkageQuery {
+    wet_height: Option<f64>,
+    wet_diameter: Option<f64>,
+    wet_volume: Option<f64>,
+    fired_height: Option<f64>,
+    fired_diameter: Option<f64>,
+    fired_volume: Option<f64>,
+    #[serde(default)]
+    unit: LengthUnit,
+    #[serde(default)]
+    volume_unit: VolumeUnit,
+}
+
+pub(crate) async fn shrinkage(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    Query(query): Query<ShrinkageQuery>,
+) -> OptionalResult<ShrinkageCalculation> {
+    sqlx::query_scalar::<_, f64>("SELECT shrinkage FROM clays WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await
+        .map(|opt_shrinkage| {
+            opt_shrinkage.map(|s| ShrinkageCalculation {
+                shrinkage: s,
+                unit: query.unit,
+                volume_unit: query.volume_unit,
+                wet: Dimensions {
+                    height: query
+                        .wet_height
+                        .or(query.fired_height.map(|h| wet_size(h, s))),
+                    diameter: query
+                        .wet_diameter
+                        .or(query.fired_diameter.map(|d| wet_size(d, s))),
+                    volume: query
+                        .wet_volume
+                        .or(query.fired_volume.map(|v| wet_volume(v, s))),
+                },
+                fired: Dimensions {
+                    height: query
+                        .fired_height
+                        .or(query.wet_height.map(|h| fired_size(h, s))),
+                    diameter: query
+                        .fired_diameter
+                        .or(query.wet_diameter.map(|d| fired_size(d, s))),
+                    volume: query
+                        .fired_volume
+                        .or(query.wet_volume.map(|v| fired_volume(v, s))),
+                },
+            })
+        })
+        .into()
+}
+
+async fn calculate_measured_shrinkage(
+    appstate: &AppState,
+    id: i32,
+) -> Result<Option<ClayShrinkage>, Error> {
+    let nominal = match sqlx::query_scalar::<_, f64>("SELECT shrinkage FROM clays WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await?
+    {
+        Some(nominal) => nominal,
+        None => return Ok(None),
+    };
+
+    let events = sqlx::query_as::<_, EventDTO>(&format!(
+        "{} {}",
+        EVENT_DTO_QUERY,
+        "JOIN works w ON e.work_id = w.id WHERE w.clay_id = ? ORDER BY e.occurred_at, e.id"
+    ))
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let mut events_by_work: BTreeMap<i32, Vec<Event>> = BTreeMap::new();
+    for event in events {
+        events_by_work
+            .entry(event.work_id)
+            .or_default()
+            .push(event.into());
+    }
+
+    let shrinkages = events_by_work
+        .values()
+        .filter_map(|events| work_shrinkage(events)?.mean())
+        .collect::<Vec<f64>>();
+    let measured = if shrinkages.is_empty() {
+        None
+    } else {
+        Some(shrinkages.iter().sum::<f64>() / shrinkages.len() as f64)
+    };
+
+    Ok(Some(ClayShrinkage {
+        clay: (ApiResource::Clay, id).into(),
+        nominal,
+        measured,
+        difference: measured.map(|m| m - nominal),
+        samples: shrinkages.len(),
+    }))
+}
+
+pub(crate) async fn measured_shrinkage(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<ClayShrinkage> {
+    OptionalResult(calculate_measured_shrinkage(&appstate, id).await)
+}
+
+#[derive(sqlx::FromRow)]
+struct StateThresholdDTO {
+    state: String,
+    max_days: f64,
+}
+
+pub(crate) async fn stale_thresholds(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<StateThreshold>> {
+    sqlx::query_as::<_, StateThresholdDTO>(
+        "SELECT s.key AS state, ct.max_days
+        FROM clay_state_thresholds ct
+        JOIN states s ON ct.state_id = s.id
+        WHERE ct.clay_id = ?
+        ORDER BY s.id",
     )
+    .bind(id)
     .fetch_all(&appstate.pool)
     .await
-    .map(|clays| clays.into_iter().map(Clay::from).collect::<Vec<Clay>>())
+    .map(|thresholds| {
+        thresholds
+            .into_iter()
+            .map(|t| StateThreshold {
+                state: t.state.into(),
+                max_days: t.max_days,
+            })
+            .collect::<Vec<StateThreshold>>()
+    })
     .into()
 }
+
+// PUT
+
+/// Why a clay can't be saved, if it can't. Shrinkage of 1 or more would
+/// leave nothing of a piece once fired.
+fn clay_error(data: &PutClay) -> Option<Error> {
+    if !(0.0..1.0).contains(&data.shrinkage) {
+        return Some(Error::InvalidShrinkage);
+    }
+    let is_valid = |value: Option<f64>| value.is_none_or(|v| v.is_finite() && v >= 0.0);
+    if !is_valid(data.coe) {
+        return Some(Error::InvalidExpansion);
+    }
+    if !is_valid(data.grog) {
+        return Some(Error::InvalidGrog);
+    }
+
+    let mut ranks = [None, None];
+    for (rank, cone) in ranks.iter_mut().zip([&data.cone_min, &data.cone_max]) {
+        if let Some(cone) = cone {
+            match cone_rank(cone) {
+                Some(r) => *rank = Some(r),
+                None => return Some(Error::InvalidCone(cone.clone())),
+            }
+        }
+    }
+    if let [Some(min), Some(max)] = ranks {
+        if min > max {
+            return Some(Error::InvalidConeRange);
+        }
+    }
+
+    None
+}
+
+async fn update_clay(appstate: &AppState, id: i32, data: &PutClay) -> Result<(), Error> {
+    if let Some(e) = clay_error(data) {
+        return Err(e);
+    }
+
+    sqlx::query(
+        "UPDATE clays
+        SET name=?, description=?, shrinkage=?, coe=?, supplier=?, cone_min=?, cone_max=?,
+        fired_colour=?, grog=?, is_active=?
+        WHERE id=?",
+    )
+    .bind(&data.name)
+    .bind(&data.description)
+    .bind(data.shrinkage)
+    .bind(data.coe)
+    .bind(&data.supplier)
+    .bind(&data.cone_min)
+    .bind(&data.cone_max)
+    .bind(&data.fired_colour)
+    .bind(data.grog)
+    .bind(data.is_active)
+    .bind(id)
+    .execute(&appstate.pool)
+    .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn put_clay(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutClay>,
+) -> EmptyResult {
+    EmptyResult(update_clay(&appstate, id, &data).await)
+}
+
+async fn replace_stale_thresholds(
+    appstate: &AppState,
+    id: i32,
+    thresholds: &[StateThreshold],
+) -> Result<(), Error> {
+    if !thresholds.iter().all(|t| is_valid_max_days(t.max_days)) {
+        return Err(Error::InvalidMaxDays);
+    }
+
+    let machine = appstate.states.read().await.clone();
+    let mut state_ids = Vec::new();
+    for threshold in thresholds {
+        let state_id = machine
+            .id(&threshold.state)
+            .ok_or_else(|| Error::UnknownState(threshold.state.key().to_string()))?;
+        state_ids.push((state_id, threshold.max_days));
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query_scalar::<_, i32>("SELECT id FROM clays WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or(Error::ResourceNotFound)?;
+
+    sqlx::query("DELETE FROM clay_state_thresholds WHERE clay_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    for (state_id, max_days) in state_ids {
+        sqlx::query(
+            "INSERT OR REPLACE INTO clay_state_thresholds (clay_id, state_id, max_days)
+            VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(state_id)
+        .bind(max_days)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_stale_thresholds(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<Vec<StateThreshold>>,
+) -> EmptyResult {
+    EmptyResult(replace_stale_thresholds(&appstate, id, &data).await)
+}
+
+// POST
+
+async fn insert_clay(appstate: &AppState, data: &PutClay) -> Result<i32, Error> {
+    if let Some(e) = clay_error(data) {
+        return Err(e);
+    }
+
+    let id = sqlx::query_scalar(
+        "INSERT INTO clays (name, description, shrinkage, coe, supplier, cone_min, cone_max,
+        fired_colour, grog, is_active)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id",
+    )
+    .bind(&data.name)
+    .bind(&data.description)
+    .bind(data.shrinkage)
+    .bind(data.coe)
+    .bind(&data.supplier)
+    .bind(&data.cone_min)
+    .bind(&data.cone_max)
+    .bind(&data.fired_colour)
+    .bind(data.grog)
+    .bind(data.is_active)
+    .fetch_one(&appstate.pool)
+    .await?;
+
+    Ok(id)
+}
+
+pub(crate) async fn post_clay(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutClay>,
+) -> JsonResult<i32> {
+    JsonResult(insert_clay(&appstate, &data).await)
+}
+
+// DELETE
+
+async fn delete_unused_clay(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    let works = sqlx::query_scalar::<_, i32>("SELECT COUNT(*) FROM works WHERE clay_id = ?")
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await?;
+
+    if works > 0 {
+        return Err(Error::ClayInUse);
+    }
+
+    sqlx::query("DELETE FROM clay_state_thresholds WHERE clay_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    let deleted = sqlx::query("DELETE FROM clays WHERE id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn delete_clay(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(delete_unused_clay(&appstate, id).await)
+}
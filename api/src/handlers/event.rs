@@ -1,28 +1,77 @@
 use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
 use chrono::NaiveDateTime;
+use futures_core::Stream;
 use serde::Deserialize;
+use sqlx::SqlitePool;
+use tracing::{event, Level};
 
-use crate::models::{ApiResource, Event, State as WorkState};
+use crate::error::Error;
+use crate::models::{ApiResource, Defect, Event, Measurements, State as WorkState};
 use crate::result::JsonResult;
 use crate::AppState;
 
-#[derive(sqlx::FromRow)]
-struct EventDTO {
-    id: i32,
-    work_id: i32,
-    previous_state_id: Option<i32>,
-    current_state_id: i32,
-    created_at: NaiveDateTime,
+pub(crate) static EVENT_DTO_QUERY: &str = "
+SELECT e.id, e.work_id, s1.key AS previous_state, s2.key AS current_state, e.occurred_at, e.created_at,
+e.height, e.width, e.weight, e.wall_thickness, e.firing_id,
+e.schedule_id, e.kiln_id, e.defect, e.defect_note, e.note,
+(
+    SELECT json_group_array(image_key)
+    FROM (SELECT image_key FROM event_images WHERE event_id = e.id ORDER BY position)
+) AS images
+FROM events e
+LEFT JOIN states s1 ON e.previous_state = s1.id
+LEFT JOIN states s2 ON e.current_state = s2.id";
+
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct EventDTO {
+    pub(crate) id: i32,
+    pub(crate) work_id: i32,
+    pub(crate) previous_state: Option<String>,
+    pub(crate) current_state: String,
+    pub(crate) occurred_at: NaiveDateTime,
+    pub(crate) created_at: NaiveDateTime,
+    pub(crate) height: Option<f64>,
+    pub(crate) width: Option<f64>,
+    pub(crate) weight: Option<f64>,
+    pub(crate) wall_thickness: Option<f64>,
+    pub(crate) firing_id: Option<i32>,
+    pub(crate) schedule_id: Option<i32>,
+    pub(crate) kiln_id: Option<i32>,
+    pub(crate) defect: Option<Defect>,
+    pub(crate) defect_note: Option<String>,
+    pub(crate) note: Option<String>,
+    /// JSON array of image keys.
+    pub(crate) images: String,
 }
 
 impl From<EventDTO> for Event {
     fn from(event: EventDTO) -> Self {
+        let measurements = Measurements {
+            height: event.height,
+            width: event.width,
+            weight: event.weight,
+            wall_thickness: event.wall_thickness,
+        };
+
         Event {
             id: event.id,
             work: (ApiResource::Work, event.work_id).into(),
-            previous_state: event.previous_state_id.map(WorkState::from),
-            current_state: event.current_state_id.into(),
+            previous_state: event.previous_state.map(WorkState::from),
+            current_state: event.current_state.into(),
+            occurred_at: event.occurred_at,
             created_at: event.created_at,
+            measurements: Some(measurements).filter(|m| !m.is_empty()),
+            firing: event.firing_id.map(|id| (ApiResource::Firing, id).into()),
+            schedule: event
+                .schedule_id
+                .map(|id| (ApiResource::Schedule, id).into()),
+            kiln: event.kiln_id.map(|id| (ApiResource::Kiln, id).into()),
+            defect: event.defect,
+            defect_note: event.defect_note,
+            note: event.note,
+            images: serde_json::from_str(&event.images).unwrap_or_default(),
         }
     }
 }
@@ -36,14 +85,9 @@ pub(crate) async fn events(
     State(appstate): State<AppState>,
     Query(limit): Query<Limit>,
 ) -> JsonResult<Vec<Event>> {
-    let mut query_string = String::from(
-        "SELECT e.id, e.work_id, s1.id AS previous_state_id,
-        s2.id AS current_state_id, e.created_at
-        FROM events e
-        LEFT JOIN states s1 ON e.previous_state = s1.id
-        LEFT JOIN states s2 ON e.current_state = s2.id
-        JOIN works w ON e.work_id = w.id
-        ORDER BY e.created_at DESC",
+    let mut query_string = format!(
+        "{} {}",
+        EVENT_DTO_QUERY, "JOIN works w ON e.work_id = w.id ORDER BY e.occurred_at DESC, e.id DESC"
     );
 
     if let Some(limit_value) = limit.limit {
@@ -58,3 +102,196 @@ pub(crate) async fn events(
         .map(|events| events.into_iter().map(Event::from).collect::<Vec<Event>>())
         .into()
 }
+
+/// Wakes up event streams, once the transaction recording or undoing events
+/// has been committed.
+pub(crate) fn notify_events(appstate: &AppState) {
+    appstate.new_events.send_replace(());
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamFilter {
+    work: Option<i32>,
+    project: Option<i32>,
+}
+
+async fn fetch_events_after(
+    pool: &SqlitePool,
+    id: i32,
+    filter: &StreamFilter,
+) -> Result<Vec<EventDTO>, sqlx::Error> {
+    sqlx::query_as::<_, EventDTO>(&format!(
+        "{} {}",
+        EVENT_DTO_QUERY,
+        "JOIN works w ON e.work_id = w.id
+        WHERE e.id > ? AND e.work_id = COALESCE(?, e.work_id)
+        AND w.project_id = COALESCE(?, w.project_id)
+        ORDER BY e.id"
+    ))
+    .bind(id)
+    .bind(filter.work)
+    .bind(filter.project)
+    .fetch_all(pool)
+    .await
+}
+
+#[derive(sqlx::FromRow)]
+struct RevertDTO {
+    id: i32,
+    /// The undone event, as it was returned by the API.
+    event: String,
+}
+
+async fn fetch_reverts_after(
+    pool: &SqlitePool,
+    id: i32,
+    filter: &StreamFilter,
+) -> Result<Vec<RevertDTO>, sqlx::Error> {
+    sqlx::query_as::<_, RevertDTO>(
+        "SELECT r.id, r.event
+        FROM event_reverts r
+        JOIN works w ON r.work_id = w.id
+        WHERE r.id > ? AND r.work_id = COALESCE(?, r.work_id)
+        AND w.project_id = COALESCE(?, w.project_id)
+        ORDER BY r.id",
+    )
+    .bind(id)
+    .bind(filter.work)
+    .bind(filter.project)
+    .fetch_all(pool)
+    .await
+}
+
+/// Where a stream is up to, given as the id of each message sent: the ids
+/// of the last event and of the last undone event it sent, separated by a
+/// colon.
+fn parse_stream_position(value: &str) -> Option<(i32, i32)> {
+    let (event_id, revert_id) = value.split_once(':')?;
+    Some((event_id.parse().ok()?, revert_id.parse().ok()?))
+}
+
+/// Pushes events as they are recorded, optionally only those of a work or
+/// of a project's works. Events are sent in the order they were recorded,
+/// and undone events are sent again as `reverted` messages so clients can
+/// drop them. A client reconnecting with `Last-Event-ID` is sent the
+/// messages it missed first.
+pub(crate) async fn stream(
+    State(appstate): State<AppState>,
+    Query(filter): Query<StreamFilter>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, serde_json::Error>>>, Error> {
+    let position = headers
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_stream_position);
+    let (mut last_id, mut last_revert_id) = match position {
+        Some(position) => position,
+        None => {
+            sqlx::query_as::<_, (i32, i32)>(
+                "SELECT (SELECT COALESCE(MAX(id), 0) FROM events),
+                (SELECT COALESCE(MAX(id), 0) FROM event_reverts)",
+            )
+            .fetch_one(&appstate.pool)
+            .await?
+        }
+    };
+
+    // Subscribing before the first fetch means events recorded in between
+    // still wake the stream up.
+    let mut receiver = appstate.new_events.subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match fetch_events_after(&appstate.pool, last_id, &filter).await {
+                Ok(events) => {
+                    for event in events {
+                        last_id = event.id;
+                        yield SseEvent::default()
+                            .id(format!("{}:{}", last_id, last_revert_id))
+                            .json_data(Event::from(event));
+                    }
+                }
+                Err(e) => {
+                    event!(Level::ERROR, source = "Event stream", err = ?e);
+                    break;
+                }
+            }
+            match fetch_reverts_after(&appstate.pool, last_revert_id, &filter).await {
+                Ok(reverts) => {
+                    for revert in reverts {
+                        last_revert_id = revert.id;
+                        yield Ok(SseEvent::default()
+                            .event("reverted")
+                            .id(format!("{}:{}", last_id, last_revert_id))
+                            .data(revert.event));
+                    }
+                }
+                Err(e) => {
+                    event!(Level::ERROR, source = "Event stream", err = ?e);
+                    break;
+                }
+            }
+            if receiver.changed().await.is_err() {
+                break;
+            }
+        }
+    };
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_pool;
+
+    async fn insert_event(pool: &SqlitePool, previous_state: Option<i32>, state: i32) -> i32 {
+        sqlx::query_scalar(
+            "INSERT INTO events (work_id, previous_state, current_state) VALUES (1, ?, ?)
+            RETURNING id",
+        )
+        .bind(previous_state)
+        .bind(state)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_stream_position() {
+        assert_eq!(parse_stream_position("12:3"), Some((12, 3)));
+        assert_eq!(parse_stream_position("12"), None);
+        assert_eq!(parse_stream_position("12:x"), None);
+    }
+
+    #[tokio::test]
+    async fn test_events_after_undo() {
+        let pool = test_pool().await;
+        sqlx::query("INSERT INTO projects (id, name) VALUES (1, 'Project')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO works (id, project_id, name, clay_id) VALUES (1, 1, 'Work', 1)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let filter = StreamFilter {
+            work: None,
+            project: None,
+        };
+
+        insert_event(&pool, None, 1).await;
+        let sent = insert_event(&pool, Some(1), 2).await;
+
+        // Undo the transition the stream has already sent, as
+        // `remove_latest_event` does, then transition the work again.
+        sqlx::query("DELETE FROM events WHERE id = ?")
+            .bind(sent)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let id = insert_event(&pool, Some(1), 3).await;
+
+        let events = fetch_events_after(&pool, sent, &filter).await.unwrap();
+        assert_eq!(events.iter().map(|e| e.id).collect::<Vec<i32>>(), vec![id]);
+    }
+}
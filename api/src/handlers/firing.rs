@@ -0,0 +1,389 @@
+use axum::extract::{Json as ExtractJson, Path, State};
+use chrono::NaiveDateTime;
+use sqlx::{Sqlite, Transaction};
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::handlers::event::notify_events;
+use crate::handlers::work::transition_work;
+use crate::models::{
+    ApiResource, ApiResourceReference, Firing, FiringType, PostComplete, PutFiring,
+    State as WorkState, Transition,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+static FIRING_DTO_QUERY: &str = "
+SELECT id, kiln_id, firing_type, target_cone, started_at, ended_at, notes, is_completed, schedule_id,
+created_at
+FROM firings
+";
+
+#[derive(sqlx::FromRow)]
+struct FiringDTO {
+    id: i32,
+    kiln_id: i32,
+    firing_type: FiringType,
+    target_cone: Option<String>,
+    started_at: Option<NaiveDateTime>,
+    ended_at: Option<NaiveDateTime>,
+    notes: Option<String>,
+    is_completed: bool,
+    schedule_id: Option<i32>,
+    created_at: NaiveDateTime,
+}
+
+fn firingdto_to_firing(firing: FiringDTO, works: Vec<ApiResourceReference>) -> Firing {
+    Firing {
+        id: firing.id,
+        kiln: (ApiResource::Kiln, firing.kiln_id).into(),
+        firing_type: firing.firing_type,
+        target_cone: firing.target_cone,
+        started_at: firing.started_at,
+        ended_at: firing.ended_at,
+        notes: firing.notes,
+        is_completed: firing.is_completed,
+        schedule: firing
+            .schedule_id
+            .map(|id| (ApiResource::Schedule, id).into()),
+        works,
+        created_at: firing.created_at,
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FiringWorkDTO {
+    firing_id: i32,
+    work_id: i32,
+}
+
+async fn fetch_firings(appstate: &AppState, id: Option<i32>) -> Result<Vec<Firing>, sqlx::Error> {
+    let firings = match id {
+        Some(id) => {
+            sqlx::query_as::<_, FiringDTO>(&format!("{} {}", FIRING_DTO_QUERY, "WHERE id = ?"))
+                .bind(id)
+                .fetch_all(&appstate.pool)
+                .await?
+        }
+        None => {
+            sqlx::query_as::<_, FiringDTO>(&format!(
+                "{} {}",
+                FIRING_DTO_QUERY, "ORDER BY created_at DESC"
+            ))
+            .fetch_all(&appstate.pool)
+            .await?
+        }
+    };
+
+    let loaded = sqlx::query_as::<_, FiringWorkDTO>("SELECT firing_id, work_id FROM firing_works")
+        .fetch_all(&appstate.pool)
+        .await?;
+
+    let mut works: HashMap<i32, Vec<ApiResourceReference>> = HashMap::new();
+    for l in loaded {
+        works
+            .entry(l.firing_id)
+            .or_default()
+            .push((ApiResource::Work, l.work_id).into());
+    }
+
+    Ok(firings
+        .into_iter()
+        .map(|f| {
+            let loaded = works.remove(&f.id).unwrap_or_default();
+            firingdto_to_firing(f, loaded)
+        })
+        .collect::<Vec<Firing>>())
+}
+
+pub(crate) async fn firings(State(appstate): State<AppState>) -> JsonResult<Vec<Firing>> {
+    fetch_firings(&appstate, None).await.into()
+}
+
+pub(crate) async fn firing(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Firing> {
+    fetch_firings(&appstate, Some(id))
+        .await
+        .map(|firings| firings.into_iter().next())
+        .into()
+}
+
+// PUT
+
+/// Checks a kiln exists, so a firing can't be given an unknown one.
+async fn check_kiln(tx: &mut Transaction<'_, Sqlite>, kiln_id: i32) -> Result<(), Error> {
+    sqlx::query_scalar::<_, i32>("SELECT id FROM kilns WHERE id = ?")
+        .bind(kiln_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(Error::ResourceNotFound)?;
+
+    Ok(())
+}
+
+/// Updates a firing, provided it hasn't been completed, as its works have
+/// then been transitioned with its kiln and schedule.
+async fn update_firing(appstate: &AppState, id: i32, data: &PutFiring) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    if is_completed(&mut tx, id).await? {
+        return Err(Error::FiringCompleted);
+    }
+    check_kiln(&mut tx, data.kiln_id).await?;
+
+    sqlx::query(
+        "UPDATE firings
+        SET kiln_id=?, firing_type=?, target_cone=?, started_at=?, ended_at=?, notes=?,
+        schedule_id=?
+        WHERE id=?",
+    )
+    .bind(data.kiln_id)
+    .bind(data.firing_type)
+    .bind(&data.target_cone)
+    .bind(data.started_at)
+    .bind(data.ended_at)
+    .bind(&data.notes)
+    .bind(data.schedule_id)
+    .bind(id)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_firing(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutFiring>,
+) -> EmptyResult {
+    EmptyResult(update_firing(&appstate, id, &data).await)
+}
+
+async fn is_completed(tx: &mut Transaction<'_, Sqlite>, id: i32) -> Result<bool, Error> {
+    sqlx::query_scalar::<_, bool>("SELECT is_completed FROM firings WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(Error::ResourceNotFound)
+}
+
+async fn load_works(appstate: &AppState, id: i32, work_ids: &[i32]) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    if is_completed(&mut tx, id).await? {
+        return Err(Error::FiringCompleted);
+    }
+
+    let firing_type =
+        sqlx::query_scalar::<_, FiringType>("SELECT firing_type FROM firings WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut tx)
+            .await?;
+
+    let mut unique_ids = Vec::new();
+    for work_id in work_ids {
+        if !unique_ids.contains(work_id) {
+            unique_ids.push(*work_id);
+        }
+    }
+
+    let (loaded_state, _) = firing_type.transition();
+    for work_id in &unique_ids {
+        let state = sqlx::query_scalar::<_, String>(
+            "SELECT s.key
+            FROM events e
+            JOIN states s ON e.current_state = s.id
+            WHERE e.work_id = ?
+            ORDER BY e.occurred_at DESC, e.id DESC
+            LIMIT 1",
+        )
+        .bind(work_id)
+        .fetch_optional(&mut tx)
+        .await?
+        .map(WorkState::from)
+        .ok_or(Error::ResourceNotFound)?;
+
+        if state != loaded_state {
+            return Err(Error::NotAwaitingFiring);
+        }
+
+        // Completing either firing would leave the other unable to complete.
+        let other_firings = sqlx::query_scalar::<_, i32>(
+            "SELECT COUNT(*)
+            FROM firing_works fw
+            JOIN firings f ON fw.firing_id = f.id
+            WHERE fw.work_id = ? AND fw.firing_id != ? AND NOT f.is_completed",
+        )
+        .bind(work_id)
+        .bind(id)
+        .fetch_one(&mut tx)
+        .await?;
+
+        if other_firings > 0 {
+            return Err(Error::AlreadyLoaded(*work_id));
+        }
+    }
+
+    sqlx::query("DELETE FROM firing_works WHERE firing_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    for work_id in unique_ids {
+        sqlx::query("INSERT INTO firing_works (firing_id, work_id) VALUES (?, ?)")
+            .bind(id)
+            .bind(work_id)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_works(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<Vec<i32>>,
+) -> EmptyResult {
+    EmptyResult(load_works(&appstate, id, &data).await)
+}
+
+// POST
+
+async fn insert_firing(appstate: &AppState, data: &PutFiring) -> Result<i32, Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    check_kiln(&mut tx, data.kiln_id).await?;
+
+    let id = sqlx::query_scalar(
+        "INSERT INTO firings (kiln_id, firing_type, target_cone, started_at, ended_at, notes,
+        schedule_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        RETURNING id",
+    )
+    .bind(data.kiln_id)
+    .bind(data.firing_type)
+    .bind(&data.target_cone)
+    .bind(data.started_at)
+    .bind(data.ended_at)
+    .bind(&data.notes)
+    .bind(data.schedule_id)
+    .fetch_one(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_firing(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutFiring>,
+) -> JsonResult<i32> {
+    JsonResult(insert_firing(&appstate, &data).await)
+}
+
+/// Completes a firing, moving every work loaded into it on to the state that
+/// follows the firing, or to `FailedInFiring` for those listed as failures.
+/// Either every work is transitioned or none are.
+async fn complete_firing(appstate: &AppState, id: i32, data: &PostComplete) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    if is_completed(&mut tx, id).await? {
+        return Err(Error::FiringCompleted);
+    }
+
+    let (firing_type, schedule_id, kiln_id) = sqlx::query_as::<_, (FiringType, Option<i32>, i32)>(
+        "SELECT firing_type, schedule_id, kiln_id FROM firings WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_one(&mut tx)
+    .await?;
+
+    let work_ids =
+        sqlx::query_scalar::<_, i32>("SELECT work_id FROM firing_works WHERE firing_id = ?")
+            .bind(id)
+            .fetch_all(&mut tx)
+            .await?;
+
+    if let Some(failure) = data
+        .failures
+        .iter()
+        .find(|f| !work_ids.contains(&f.work_id))
+    {
+        return Err(Error::NotInFiring(failure.work_id));
+    }
+
+    let machine = appstate.states.read().await.clone();
+    let (_, fired_state) = firing_type.transition();
+    for work_id in work_ids {
+        let failure = data.failures.iter().find(|f| f.work_id == work_id);
+        let transition = Transition {
+            state: match failure {
+                Some(_) => WorkState::FAILED_IN_FIRING,
+                None => fired_state.clone(),
+            },
+            measurements: None,
+            schedule_id,
+            kiln_id: Some(kiln_id),
+            defect: failure.map(|f| f.defect),
+            defect_note: failure.and_then(|f| f.note.clone()),
+            note: None,
+            images: Vec::new(),
+            occurred_at: None,
+        };
+        transition_work(&mut tx, &machine, work_id, &transition, Some(id)).await?;
+    }
+
+    sqlx::query(
+        "UPDATE firings
+        SET is_completed = 1, ended_at = COALESCE(ended_at, strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+        WHERE id = ?",
+    )
+    .bind(id)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    notify_events(appstate);
+    Ok(())
+}
+
+pub(crate) async fn post_complete(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    data: Option<ExtractJson<PostComplete>>,
+) -> EmptyResult {
+    let data = data.map(|ExtractJson(data)| data).unwrap_or_default();
+    EmptyResult(complete_firing(&appstate, id, &data).await)
+}
+
+// DELETE
+
+/// Removes a firing, provided it hasn't been completed, as the events of
+/// the works fired in it refer to it.
+async fn remove_firing(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    if is_completed(&mut tx, id).await? {
+        return Err(Error::FiringCompleted);
+    }
+
+    sqlx::query("DELETE FROM firings WHERE id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn delete_firing(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(remove_firing(&appstate, id).await)
+}
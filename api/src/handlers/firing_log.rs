@@ -0,0 +1,265 @@
+use axum::extract::{Path, Query, State};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::firing_log::{downsample, parse_csv, statistics};
+use crate::models::{ApiResource, FiringLog, State as WorkState, TemperaturePoint, ZoneSeries};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+/// Number of points each zone's series is thinned down to by default.
+const DEFAULT_POINTS: usize = 500;
+
+#[derive(sqlx::FromRow)]
+struct FiringLogDTO {
+    id: i32,
+    firing_id: Option<i32>,
+    name: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+#[derive(sqlx::FromRow)]
+struct ReadingDTO {
+    zone: i32,
+    recorded_at: NaiveDateTime,
+    temperature: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeriesLength {
+    points: Option<usize>,
+}
+
+/// Averages the zones of each reading into a single series.
+fn mean_series(readings: &[ReadingDTO]) -> Vec<TemperaturePoint> {
+    let mut points: Vec<(TemperaturePoint, usize)> = Vec::new();
+    for reading in readings {
+        match points.last_mut() {
+            Some((point, count)) if point.time == reading.recorded_at => {
+                point.temperature += reading.temperature;
+                *count += 1;
+            }
+            _ => points.push((
+                TemperaturePoint {
+                    time: reading.recorded_at,
+                    temperature: reading.temperature,
+                },
+                1,
+            )),
+        }
+    }
+
+    points
+        .into_iter()
+        .map(|(point, count)| TemperaturePoint {
+            time: point.time,
+            temperature: point.temperature / count as f64,
+        })
+        .collect()
+}
+
+async fn fetch_firing_log(
+    appstate: &AppState,
+    id: i32,
+    points: usize,
+) -> Result<Option<FiringLog>, Error> {
+    let log = match sqlx::query_as::<_, FiringLogDTO>(
+        "SELECT id, firing_id, name, created_at FROM firing_logs WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(&appstate.pool)
+    .await?
+    {
+        Some(log) => log,
+        None => return Ok(None),
+    };
+
+    let zones = sqlx::query_scalar::<_, String>(
+        "SELECT name FROM firing_log_zones WHERE log_id = ? ORDER BY position",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let readings = sqlx::query_as::<_, ReadingDTO>(
+        "SELECT zone, recorded_at, temperature
+        FROM firing_log_readings
+        WHERE log_id = ?
+        ORDER BY recorded_at, zone",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let work_ids = sqlx::query_scalar::<_, i32>(
+        "SELECT DISTINCT e.work_id
+        FROM firing_log_events fle
+        JOIN events e ON fle.event_id = e.id
+        WHERE fle.log_id = ?
+        ORDER BY e.work_id",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let mean = mean_series(&readings);
+    let (started_at, ended_at) = match (mean.first(), mean.last()) {
+        (Some(first), Some(last)) => (first.time, last.time),
+        _ => return Err(Error::InvalidFiringLog(0)),
+    };
+
+    let zones = zones
+        .into_iter()
+        .enumerate()
+        .map(|(position, name)| {
+            let series = readings
+                .iter()
+                .filter(|r| r.zone == position as i32)
+                .map(|r| TemperaturePoint {
+                    time: r.recorded_at,
+                    temperature: r.temperature,
+                })
+                .collect::<Vec<TemperaturePoint>>();
+            ZoneSeries {
+                name,
+                points: downsample(&series, points),
+            }
+        })
+        .collect::<Vec<ZoneSeries>>();
+
+    Ok(Some(FiringLog {
+        id: log.id,
+        name: log.name,
+        firing: log.firing_id.map(|id| (ApiResource::Firing, id).into()),
+        started_at,
+        ended_at,
+        works: work_ids
+            .into_iter()
+            .map(|id| (ApiResource::Work, id).into())
+            .collect(),
+        zones,
+        statistics: statistics(&mean),
+        created_at: log.created_at,
+    }))
+}
+
+pub(crate) async fn firing_log(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    Query(length): Query<SeriesLength>,
+) -> OptionalResult<FiringLog> {
+    let points = length.points.unwrap_or(DEFAULT_POINTS);
+    OptionalResult(fetch_firing_log(&appstate, id, points).await)
+}
+
+// POST
+
+#[derive(Debug, Deserialize)]
+pub struct LogUpload {
+    firing_id: Option<i32>,
+    name: Option<String>,
+}
+
+/// Stores a controller's CSV log and links it to the transitions out of a
+/// firing state that it covers. A log uploaded against a firing covers the
+/// transitions made by completing that firing; otherwise it covers those
+/// recorded between the start of the log and two days after it ends, to allow
+/// for the kiln cooling before it is unloaded.
+async fn insert_firing_log(
+    appstate: &AppState,
+    upload: &LogUpload,
+    csv: &str,
+) -> Result<i32, Error> {
+    let log = parse_csv(csv).map_err(Error::InvalidFiringLog)?;
+
+    let mut tx = appstate.pool.begin().await?;
+
+    if let Some(firing_id) = upload.firing_id {
+        sqlx::query_scalar::<_, i32>("SELECT id FROM firings WHERE id = ?")
+            .bind(firing_id)
+            .fetch_optional(&mut tx)
+            .await?
+            .ok_or(Error::ResourceNotFound)?;
+    }
+
+    let id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO firing_logs (firing_id, name)
+        VALUES (?, ?)
+        RETURNING id",
+    )
+    .bind(upload.firing_id)
+    .bind(&upload.name)
+    .fetch_one(&mut tx)
+    .await?;
+
+    for (position, name) in log.zones.iter().enumerate() {
+        sqlx::query("INSERT INTO firing_log_zones (log_id, position, name) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(position as i32)
+            .bind(name)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    for reading in &log.readings {
+        for (zone, temperature) in reading.temperatures.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO firing_log_readings (log_id, zone, recorded_at, temperature)
+                VALUES (?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(zone as i32)
+            .bind(reading.recorded_at)
+            .bind(temperature)
+            .execute(&mut tx)
+            .await?;
+        }
+    }
+
+    let started_at = log.readings[0].recorded_at;
+    let ended_at = log.readings[log.readings.len() - 1].recorded_at;
+    sqlx::query(
+        "INSERT INTO firing_log_events (log_id, event_id)
+        SELECT ?, id
+        FROM events
+        WHERE previous_state IN (SELECT id FROM states WHERE key IN (?, ?))
+        AND CASE WHEN ? IS NULL
+            THEN datetime(occurred_at) BETWEEN datetime(?) AND datetime(?, '+2 days')
+            ELSE firing_id = ?
+        END",
+    )
+    .bind(id)
+    .bind(WorkState::AWAITING_BISQUE_FIRING.key())
+    .bind(WorkState::AWAITING_GLAZE_FIRING.key())
+    .bind(upload.firing_id)
+    .bind(started_at)
+    .bind(ended_at)
+    .bind(upload.firing_id)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_firing_log(
+    State(appstate): State<AppState>,
+    Query(upload): Query<LogUpload>,
+    csv: String,
+) -> JsonResult<i32> {
+    JsonResult(insert_firing_log(&appstate, &upload, &csv).await)
+}
+
+// DELETE
+
+pub(crate) async fn delete_firing_log(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    sqlx::query("DELETE FROM firing_logs WHERE id = ?")
+        .bind(id)
+        .execute(&appstate.pool)
+        .await
+        .into()
+}
@@ -0,0 +1,416 @@
+use axum::extract::{Json as ExtractJson, Path, Query, State};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::chemistry::estimated_coe;
+use crate::error::Error;
+use crate::handlers::material::recipe_umf;
+use crate::handlers::search::{reindex, unindex};
+use crate::models::{
+    invalid_oxide, ApplicationMethod, Batch, Glaze, GlazeLayer, Ingredient, Oxide, OxideAnalysis,
+    PutGlaze, Recipe, SearchResource, Surface, Umf,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+static GLAZE_DTO_QUERY: &str = "
+SELECT id, name, description, cone_min, cone_max, surface, coe
+FROM glazes
+";
+
+#[derive(sqlx::FromRow)]
+struct GlazeDTO {
+    id: i32,
+    name: String,
+    description: Option<String>,
+    cone_min: Option<String>,
+    cone_max: Option<String>,
+    surface: Option<Surface>,
+    coe: Option<f64>,
+}
+
+impl From<GlazeDTO> for Glaze {
+    fn from(glaze: GlazeDTO) -> Self {
+        Glaze {
+            id: glaze.id,
+            name: glaze.name,
+            description: glaze.description,
+            cone_min: glaze.cone_min,
+            cone_max: glaze.cone_max,
+            surface: glaze.surface,
+            coe: glaze.coe,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct GlazeLayerDTO {
+    work_id: i32,
+    method: Option<ApplicationMethod>,
+    coats: i32,
+    area: Option<String>,
+    #[sqlx(flatten)]
+    glaze: GlazeDTO,
+}
+
+/// Fetches the glaze layers of the given works, keyed by work id and in the
+/// order they were applied.
+pub(crate) async fn glaze_layers(
+    appstate: &AppState,
+    work_ids: &[i32],
+) -> Result<HashMap<i32, Vec<GlazeLayer>>, sqlx::Error> {
+    let layers = sqlx::query_as::<_, GlazeLayerDTO>(
+        "SELECT wg.work_id, wg.method, wg.coats, wg.area,
+        g.id, g.name, g.description, g.cone_min, g.cone_max, g.surface, g.coe
+        FROM work_glazes wg
+        JOIN glazes g ON wg.glaze_id = g.id
+        WHERE wg.work_id IN (SELECT value FROM json_each(?))
+        ORDER BY wg.work_id, wg.layer",
+    )
+    .bind(serde_json::to_string(work_ids).unwrap_or_default())
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let mut by_work: HashMap<i32, Vec<GlazeLayer>> = HashMap::new();
+    for layer in layers {
+        by_work.entry(layer.work_id).or_default().push(GlazeLayer {
+            glaze: layer.glaze.into(),
+            method: layer.method,
+            coats: layer.coats,
+            area: layer.area,
+            fit: None,
+        });
+    }
+
+    Ok(by_work)
+}
+
+pub(crate) async fn fetch_glazes(appstate: &AppState) -> Result<Vec<Glaze>, sqlx::Error> {
+    sqlx::query_as::<_, GlazeDTO>(GLAZE_DTO_QUERY)
+        .fetch_all(&appstate.pool)
+        .await
+        .map(|glazes| glazes.into_iter().map(Glaze::from).collect::<Vec<Glaze>>())
+}
+
+pub(crate) async fn glazes(State(appstate): State<AppState>) -> JsonResult<Vec<Glaze>> {
+    fetch_glazes(&appstate).await.into()
+}
+
+pub(crate) async fn glaze(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Glaze> {
+    sqlx::query_as::<_, GlazeDTO>(&format!("{} {}", GLAZE_DTO_QUERY, "WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await
+        .map(|opt_glaze| opt_glaze.map(Glaze::from))
+        .into()
+}
+
+#[derive(sqlx::FromRow)]
+struct IngredientDTO {
+    material: String,
+    percentage: f64,
+    is_addition: bool,
+}
+
+async fn fetch_recipe(appstate: &AppState, id: i32) -> Result<Option<Recipe>, Error> {
+    let ingredients = sqlx::query_as::<_, IngredientDTO>(
+        "SELECT material, percentage, is_addition
+        FROM glaze_ingredients
+        WHERE glaze_id = ?
+        ORDER BY position",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    if ingredients.is_empty() {
+        return Ok(None);
+    }
+
+    let (additions, ingredients): (Vec<IngredientDTO>, Vec<IngredientDTO>) =
+        ingredients.into_iter().partition(|i| i.is_addition);
+    let to_ingredient = |i: IngredientDTO| Ingredient {
+        material: i.material,
+        percentage: i.percentage,
+    };
+
+    Ok(Some(Recipe {
+        ingredients: ingredients.into_iter().map(to_ingredient).collect(),
+        additions: additions.into_iter().map(to_ingredient).collect(),
+    }))
+}
+
+pub(crate) async fn recipe(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Recipe> {
+    OptionalResult(fetch_recipe(&appstate, id).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchSize {
+    grams: f64,
+}
+
+async fn calculate_batch(appstate: &AppState, id: i32, grams: f64) -> Result<Option<Batch>, Error> {
+    if !(grams.is_finite() && grams > 0.0) {
+        return Err(Error::InvalidBatchSize);
+    }
+
+    Ok(fetch_recipe(appstate, id)
+        .await?
+        .map(|recipe| recipe.batch(grams)))
+}
+
+pub(crate) async fn batch(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    Query(size): Query<BatchSize>,
+) -> OptionalResult<Batch> {
+    OptionalResult(calculate_batch(&appstate, id, size.grams).await)
+}
+
+async fn calculate_glaze_umf(appstate: &AppState, id: i32) -> Result<Option<Umf>, Error> {
+    match fetch_recipe(appstate, id).await? {
+        Some(recipe) => recipe_umf(appstate, &recipe).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+pub(crate) async fn glaze_umf(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Umf> {
+    OptionalResult(calculate_glaze_umf(&appstate, id).await)
+}
+
+#[derive(sqlx::FromRow)]
+struct GlazeOxideDTO {
+    oxide: Oxide,
+    percentage: f64,
+}
+
+pub(crate) async fn analysis(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<OxideAnalysis> {
+    sqlx::query_as::<_, GlazeOxideDTO>(
+        "SELECT oxide, percentage
+        FROM glaze_oxides
+        WHERE glaze_id = ?",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await
+    .map(|oxides| {
+        Some(
+            oxides
+                .into_iter()
+                .map(|o| (o.oxide, o.percentage))
+                .collect::<OxideAnalysis>(),
+        )
+        .filter(|analysis| !analysis.is_empty())
+    })
+    .into()
+}
+
+// PUT
+
+async fn update_glaze(appstate: &AppState, id: i32, data: &PutGlaze) -> Result<(), sqlx::Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE glazes
+        SET name=?, description=?, cone_min=?, cone_max=?, surface=?
+        WHERE id=?",
+    )
+    .bind(&data.name)
+    .bind(&data.description)
+    .bind(&data.cone_min)
+    .bind(&data.cone_max)
+    .bind(&data.surface)
+    .bind(id)
+    .execute(&mut tx)
+    .await?;
+
+    reindex(&mut tx, SearchResource::Glaze, id).await?;
+    tx.commit().await
+}
+
+pub(crate) async fn put_glaze(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutGlaze>,
+) -> EmptyResult {
+    update_glaze(&appstate, id, &data).await.into()
+}
+
+async fn replace_recipe(appstate: &AppState, id: i32, recipe: &Recipe) -> Result<(), Error> {
+    if let Some(ingredient) = recipe.invalid_ingredient() {
+        return Err(Error::InvalidPercentage(ingredient.material.clone()));
+    }
+    if !recipe.is_valid() {
+        return Err(Error::InvalidRecipe {
+            base_total: recipe.base_total(),
+        });
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query_scalar::<_, i32>("SELECT id FROM glazes WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or(Error::ResourceNotFound)?;
+
+    sqlx::query("DELETE FROM glaze_ingredients WHERE glaze_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    let ingredients = recipe
+        .ingredients
+        .iter()
+        .map(|i| (i, false))
+        .chain(recipe.additions.iter().map(|i| (i, true)));
+    for (position, (ingredient, is_addition)) in ingredients.enumerate() {
+        sqlx::query(
+            "INSERT INTO glaze_ingredients (glaze_id, position, material, percentage, is_addition)
+            VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(position as i32)
+        .bind(&ingredient.material)
+        .bind(ingredient.percentage)
+        .bind(is_addition)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_recipe(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<Recipe>,
+) -> EmptyResult {
+    EmptyResult(replace_recipe(&appstate, id, &data).await)
+}
+
+async fn replace_analysis(
+    appstate: &AppState,
+    id: i32,
+    analysis: &OxideAnalysis,
+) -> Result<(), Error> {
+    if let Some(oxide) = invalid_oxide(analysis) {
+        return Err(Error::InvalidPercentage(format!("{:?}", oxide)));
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query_scalar::<_, i32>("SELECT id FROM glazes WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or(Error::ResourceNotFound)?;
+
+    sqlx::query("DELETE FROM glaze_oxides WHERE glaze_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    for (oxide, percentage) in analysis {
+        sqlx::query(
+            "INSERT INTO glaze_oxides (glaze_id, oxide, percentage)
+            VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(oxide)
+        .bind(percentage)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE glazes SET coe=? WHERE id=?")
+        .bind(estimated_coe(analysis))
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_analysis(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<OxideAnalysis>,
+) -> EmptyResult {
+    EmptyResult(replace_analysis(&appstate, id, &data).await)
+}
+
+// POST
+
+async fn insert_glaze(appstate: &AppState, data: &PutGlaze) -> Result<i32, sqlx::Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    let id = sqlx::query_scalar(
+        "INSERT INTO glazes (name, description, cone_min, cone_max, surface)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING id",
+    )
+    .bind(&data.name)
+    .bind(&data.description)
+    .bind(&data.cone_min)
+    .bind(&data.cone_max)
+    .bind(&data.surface)
+    .fetch_one(&mut tx)
+    .await?;
+
+    reindex(&mut tx, SearchResource::Glaze, id).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_glaze(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutGlaze>,
+) -> JsonResult<i32> {
+    insert_glaze(&appstate, &data).await.into()
+}
+
+// DELETE
+
+async fn remove_glaze(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let works = sqlx::query_scalar::<_, i32>("SELECT COUNT(*) FROM work_glazes WHERE glaze_id = ?")
+        .bind(id)
+        .fetch_one(&appstate.pool)
+        .await?;
+
+    if works > 0 {
+        return Err(Error::GlazeInUse);
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("DELETE FROM glazes WHERE id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    unindex(&mut tx, SearchResource::Glaze, id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn delete_glaze(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    remove_glaze(&appstate, id).await.into()
+}
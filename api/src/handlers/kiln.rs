@@ -0,0 +1,175 @@
+use axum::extract::{Json as ExtractJson, Path, Query, State};
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::handlers::work::{fetch_works, WorkDTO, WORK_DTO_QUERY};
+use crate::kiln_load::{plan_load, LoadItem};
+use crate::models::{ApiResource, ApiResourceReference, FiringType, Kiln, KilnLoad, PutKiln};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+static KILN_DTO_QUERY: &str = "
+SELECT id, name, description
+FROM kilns
+";
+
+#[derive(sqlx::FromRow)]
+struct KilnDTO {
+    id: i32,
+    name: String,
+    description: Option<String>,
+}
+
+impl From<KilnDTO> for Kiln {
+    fn from(kiln: KilnDTO) -> Self {
+        Kiln {
+            id: kiln.id,
+            name: kiln.name,
+            description: kiln.description,
+        }
+    }
+}
+
+pub(crate) async fn kilns(State(appstate): State<AppState>) -> JsonResult<Vec<Kiln>> {
+    sqlx::query_as::<_, KilnDTO>(KILN_DTO_QUERY)
+        .fetch_all(&appstate.pool)
+        .await
+        .map(|kilns| kilns.into_iter().map(Kiln::from).collect::<Vec<Kiln>>())
+        .into()
+}
+
+pub(crate) async fn kiln(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Kiln> {
+    sqlx::query_as::<_, KilnDTO>(&format!("{} {}", KILN_DTO_QUERY, "WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await
+        .map(|opt_kiln| opt_kiln.map(Kiln::from))
+        .into()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoadQuery {
+    firing_type: FiringType,
+}
+
+/// Proposes a load for the next firing from every work awaiting it, those
+/// that have waited longest first.
+async fn plan(appstate: &AppState, firing_type: FiringType) -> Result<KilnLoad, Error> {
+    let kiln = appstate
+        .config
+        .kiln
+        .as_ref()
+        .ok_or(Error::KilnNotConfigured)?;
+
+    let (loaded_state, _) = firing_type.transition();
+    let query = format!(
+        "{} {}",
+        WORK_DTO_QUERY, "WHERE e.current_state = ? ORDER BY e.current_state_transitioned, w.id"
+    );
+    let works = fetch_works(
+        appstate,
+        sqlx::query_as::<_, WorkDTO>(&query).bind(loaded_state.key()),
+    )
+    .await?;
+
+    let mut items = Vec::new();
+    let mut unmeasured: Vec<ApiResourceReference> = Vec::new();
+    for work in works {
+        match (
+            work.footprint_width_cm,
+            work.footprint_depth_cm,
+            work.height_cm,
+        ) {
+            (Some(width), Some(depth), Some(height)) => items.push(LoadItem {
+                id: work.id,
+                width,
+                depth,
+                height,
+            }),
+            _ => unmeasured.push((ApiResource::Work, work.id).into()),
+        }
+    }
+
+    let (shelves, leftover) = plan_load(kiln, &items);
+
+    Ok(KilnLoad {
+        firing_type,
+        shelves,
+        leftover: leftover
+            .into_iter()
+            .map(|id| (ApiResource::Work, id).into())
+            .collect(),
+        unmeasured,
+    })
+}
+
+pub(crate) async fn load_plan(
+    State(appstate): State<AppState>,
+    Query(load): Query<LoadQuery>,
+) -> JsonResult<KilnLoad> {
+    JsonResult(plan(&appstate, load.firing_type).await)
+}
+
+// PUT
+
+pub(crate) async fn put_kiln(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutKiln>,
+) -> EmptyResult {
+    sqlx::query("UPDATE kilns SET name=?, description=? WHERE id=?")
+        .bind(data.name)
+        .bind(data.description)
+        .bind(id)
+        .execute(&appstate.pool)
+        .await
+        .into()
+}
+
+// POST
+
+pub(crate) async fn post_kiln(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutKiln>,
+) -> JsonResult<i32> {
+    sqlx::query_scalar(
+        "INSERT INTO kilns (name, description)
+        VALUES (?, ?)
+        RETURNING id",
+    )
+    .bind(data.name)
+    .bind(data.description)
+    .fetch_one(&appstate.pool)
+    .await
+    .into()
+}
+
+// DELETE
+
+async fn delete_unused_kiln(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let firings = sqlx::query_scalar::<_, i32>("SELECT COUNT(*) FROM firings WHERE kiln_id = ?")
+        .bind(id)
+        .fetch_one(&appstate.pool)
+        .await?;
+
+    if firings > 0 {
+        return Err(Error::KilnInUse);
+    }
+
+    sqlx::query("DELETE FROM kilns WHERE id = ?")
+        .bind(id)
+        .execute(&appstate.pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_kiln(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(delete_unused_kiln(&appstate, id).await)
+}
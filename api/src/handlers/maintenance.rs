@@ -0,0 +1,209 @@
+use axum::extract::{Json as ExtractJson, Path, State};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::models::{
+    maintenance_counters, ApiResource, KilnCounters, Maintenance, MaintenanceKind, PutMaintenance,
+    State as WorkState,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct MaintenanceDTO {
+    id: i32,
+    kiln_id: i32,
+    kind: MaintenanceKind,
+    performed_at: NaiveDateTime,
+    notes: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+impl From<MaintenanceDTO> for Maintenance {
+    fn from(maintenance: MaintenanceDTO) -> Self {
+        Maintenance {
+            id: maintenance.id,
+            kiln: (ApiResource::Kiln, maintenance.kiln_id).into(),
+            kind: maintenance.kind,
+            performed_at: maintenance.performed_at,
+            notes: maintenance.notes,
+            created_at: maintenance.created_at,
+        }
+    }
+}
+
+async fn fetch_maintenance(
+    appstate: &AppState,
+    kiln_id: Option<i32>,
+) -> Result<Vec<MaintenanceDTO>, sqlx::Error> {
+    sqlx::query_as::<_, MaintenanceDTO>(
+        "SELECT id, kiln_id, kind, performed_at, notes, created_at
+        FROM kiln_maintenance
+        WHERE ? IS NULL OR kiln_id = ?
+        ORDER BY performed_at DESC",
+    )
+    .bind(kiln_id)
+    .bind(kiln_id)
+    .fetch_all(&appstate.pool)
+    .await
+}
+
+pub(crate) async fn maintenance(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<Maintenance>> {
+    fetch_maintenance(&appstate, Some(id))
+        .await
+        .map(|history| {
+            history
+                .into_iter()
+                .map(Maintenance::from)
+                .collect::<Vec<Maintenance>>()
+        })
+        .into()
+}
+
+#[derive(sqlx::FromRow)]
+struct KilnFiringDTO {
+    kiln_id: i32,
+    fired_at: NaiveDateTime,
+}
+
+/// Derives the maintenance counters of each kiln. Works leaving a firing state
+/// through a firing count towards that one firing; those transitioned by hand
+/// count as one firing per kiln per day.
+async fn fetch_counters(
+    appstate: &AppState,
+    kiln_id: Option<i32>,
+) -> Result<Vec<KilnCounters>, sqlx::Error> {
+    let kiln_ids =
+        sqlx::query_scalar::<_, i32>("SELECT id FROM kilns WHERE ? IS NULL OR id = ? ORDER BY id")
+            .bind(kiln_id)
+            .bind(kiln_id)
+            .fetch_all(&appstate.pool)
+            .await?;
+
+    let firings = sqlx::query_as::<_, KilnFiringDTO>(
+        "SELECT kiln_id, MIN(occurred_at) AS fired_at
+        FROM events
+        WHERE kiln_id IS NOT NULL
+        AND previous_state IN (SELECT id FROM states WHERE key IN (?, ?))
+        GROUP BY kiln_id, COALESCE('firing ' || firing_id, date(occurred_at))",
+    )
+    .bind(WorkState::AWAITING_BISQUE_FIRING.key())
+    .bind(WorkState::AWAITING_GLAZE_FIRING.key())
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let mut fired_at: HashMap<i32, Vec<NaiveDateTime>> = HashMap::new();
+    for firing in firings {
+        fired_at
+            .entry(firing.kiln_id)
+            .or_default()
+            .push(firing.fired_at);
+    }
+
+    let mut history: HashMap<i32, Vec<(MaintenanceKind, NaiveDateTime)>> = HashMap::new();
+    for m in fetch_maintenance(appstate, kiln_id).await? {
+        history
+            .entry(m.kiln_id)
+            .or_default()
+            .push((m.kind, m.performed_at));
+    }
+
+    Ok(kiln_ids
+        .into_iter()
+        .map(|id| {
+            let fired_at = fired_at.remove(&id).unwrap_or_default();
+            let history = history.remove(&id).unwrap_or_default();
+            KilnCounters {
+                kiln: (ApiResource::Kiln, id).into(),
+                firings: fired_at.len(),
+                counters: maintenance_counters(&fired_at, &history, &appstate.config.maintenance),
+            }
+        })
+        .collect::<Vec<KilnCounters>>())
+}
+
+pub(crate) async fn counters(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<KilnCounters> {
+    fetch_counters(&appstate, Some(id))
+        .await
+        .map(|counters| counters.into_iter().next())
+        .into()
+}
+
+/// Kilns with maintenance due, each with only the counters that are due.
+pub(crate) async fn maintenance_due(
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<KilnCounters>> {
+    fetch_counters(&appstate, None)
+        .await
+        .map(|kilns| {
+            kilns
+                .into_iter()
+                .map(|mut kiln| {
+                    kiln.counters.retain(|c| c.is_due);
+                    kiln
+                })
+                .filter(|kiln| !kiln.counters.is_empty())
+                .collect::<Vec<KilnCounters>>()
+        })
+        .into()
+}
+
+// POST
+
+async fn insert_maintenance(
+    appstate: &AppState,
+    id: i32,
+    data: &PutMaintenance,
+) -> Result<i32, Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query_scalar::<_, i32>("SELECT id FROM kilns WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or(Error::ResourceNotFound)?;
+
+    let maintenance_id = sqlx::query_scalar(
+        "INSERT INTO kiln_maintenance (kiln_id, kind, performed_at, notes)
+        VALUES (?, ?, COALESCE(?, strftime('%Y-%m-%dT%H:%M:%f', 'now')), ?)
+        RETURNING id",
+    )
+    .bind(id)
+    .bind(data.kind)
+    .bind(data.performed_at)
+    .bind(&data.notes)
+    .fetch_one(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(maintenance_id)
+}
+
+pub(crate) async fn post_maintenance(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutMaintenance>,
+) -> JsonResult<i32> {
+    JsonResult(insert_maintenance(&appstate, id, &data).await)
+}
+
+// DELETE
+
+pub(crate) async fn delete_maintenance(
+    Path((id, maintenance_id)): Path<(i32, i32)>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    sqlx::query("DELETE FROM kiln_maintenance WHERE id = ? AND kiln_id = ?")
+        .bind(maintenance_id)
+        .bind(id)
+        .execute(&appstate.pool)
+        .await
+        .into()
+}
@@ -0,0 +1,198 @@
+use axum::extract::{Json as ExtractJson, Path, State};
+use std::collections::HashMap;
+
+use crate::chemistry::unity_formula;
+use crate::error::Error;
+use crate::models::{invalid_oxide, Material, Oxide, OxideAnalysis, PutMaterial, Recipe, Umf};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct MaterialDTO {
+    id: i32,
+    name: String,
+    loi: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct MaterialOxideDTO {
+    material_id: i32,
+    oxide: Oxide,
+    percentage: f64,
+}
+
+async fn fetch_materials(appstate: &AppState) -> Result<Vec<Material>, sqlx::Error> {
+    let materials = sqlx::query_as::<_, MaterialDTO>("SELECT id, name, loi FROM materials")
+        .fetch_all(&appstate.pool)
+        .await?;
+
+    let oxides = sqlx::query_as::<_, MaterialOxideDTO>(
+        "SELECT material_id, oxide, percentage
+        FROM material_oxides",
+    )
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let mut analyses: HashMap<i32, OxideAnalysis> = HashMap::new();
+    for oxide in oxides {
+        analyses
+            .entry(oxide.material_id)
+            .or_default()
+            .insert(oxide.oxide, oxide.percentage);
+    }
+
+    Ok(materials
+        .into_iter()
+        .map(|m| Material {
+            analysis: analyses.remove(&m.id).unwrap_or_default(),
+            id: m.id,
+            name: m.name,
+            loi: m.loi,
+        })
+        .collect::<Vec<Material>>())
+}
+
+/// Calculates the unity formula of a recipe, looking up the analysis of each
+/// of its materials by name.
+pub(crate) async fn recipe_umf(appstate: &AppState, recipe: &Recipe) -> Result<Umf, Error> {
+    if let Some(ingredient) = recipe.invalid_ingredient() {
+        return Err(Error::InvalidPercentage(ingredient.material.clone()));
+    }
+
+    let materials = fetch_materials(appstate).await?;
+    let analyses = materials
+        .iter()
+        .map(|m| (m.name.to_lowercase(), &m.analysis))
+        .collect::<HashMap<String, &OxideAnalysis>>();
+
+    let mut pairs = Vec::new();
+    for ingredient in recipe.ingredients.iter().chain(recipe.additions.iter()) {
+        let analysis = analyses
+            .get(&ingredient.material.to_lowercase())
+            .ok_or_else(|| Error::UnknownMaterial(ingredient.material.clone()))?;
+        pairs.push((ingredient.percentage, *analysis));
+    }
+
+    unity_formula(&pairs).ok_or(Error::UnfluxedRecipe)
+}
+
+pub(crate) async fn materials(State(appstate): State<AppState>) -> JsonResult<Vec<Material>> {
+    fetch_materials(&appstate).await.into()
+}
+
+pub(crate) async fn material(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Material> {
+    fetch_materials(&appstate)
+        .await
+        .map(|materials| materials.into_iter().find(|m| m.id == id))
+        .into()
+}
+
+pub(crate) async fn umf(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<Recipe>,
+) -> JsonResult<Umf> {
+    JsonResult(recipe_umf(&appstate, &data).await)
+}
+
+// PUT
+
+async fn insert_analysis(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    id: i32,
+    analysis: &OxideAnalysis,
+) -> Result<(), sqlx::Error> {
+    for (oxide, percentage) in analysis {
+        sqlx::query(
+            "INSERT INTO material_oxides (material_id, oxide, percentage)
+            VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(oxide)
+        .bind(percentage)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn update_material(appstate: &AppState, id: i32, data: &PutMaterial) -> Result<(), Error> {
+    if let Some(oxide) = invalid_oxide(&data.analysis) {
+        return Err(Error::InvalidPercentage(format!("{:?}", oxide)));
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("UPDATE materials SET name=?, loi=? WHERE id=?")
+        .bind(&data.name)
+        .bind(data.loi)
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    sqlx::query("DELETE FROM material_oxides WHERE material_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    insert_analysis(&mut tx, id, &data.analysis).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_material(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutMaterial>,
+) -> EmptyResult {
+    EmptyResult(update_material(&appstate, id, &data).await)
+}
+
+// POST
+
+async fn insert_material(appstate: &AppState, data: &PutMaterial) -> Result<i32, Error> {
+    if let Some(oxide) = invalid_oxide(&data.analysis) {
+        return Err(Error::InvalidPercentage(format!("{:?}", oxide)));
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO materials (name, loi)
+        VALUES (?, ?)
+        RETURNING id",
+    )
+    .bind(&data.name)
+    .bind(data.loi)
+    .fetch_one(&mut tx)
+    .await?;
+
+    insert_analysis(&mut tx, id, &data.analysis).await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_material(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutMaterial>,
+) -> JsonResult<i32> {
+    JsonResult(insert_material(&appstate, &data).await)
+}
+
+// DELETE
+
+pub(crate) async fn delete_material(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    sqlx::query("DELETE FROM materials WHERE id = ?")
+        .bind(id)
+        .execute(&appstate.pool)
+        .await
+        .into()
+}
@@ -1,6 +1,19 @@
 pub mod auth;
 pub mod clay;
 pub mod event;
+pub mod firing;
+pub mod firing_log;
+pub mod glaze;
 pub mod image;
+pub mod kiln;
+pub mod maintenance;
+pub mod material;
+pub mod notification;
 pub mod project;
+pub mod schedule;
+pub mod search;
+pub mod state;
+pub mod stats;
+pub mod tag;
+pub mod webhook;
 pub mod work;
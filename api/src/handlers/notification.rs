@@ -0,0 +1,56 @@
+use axum::extract::State;
+use chrono::NaiveDateTime;
+
+use crate::models::{Notification, NotificationChannel, NotificationStatus};
+use crate::result::JsonResult;
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct NotificationDTO {
+    id: i32,
+    key: String,
+    channel: NotificationChannel,
+    subject: String,
+    body: String,
+    status: NotificationStatus,
+    attempts: i32,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    sent_at: Option<NaiveDateTime>,
+}
+
+impl From<NotificationDTO> for Notification {
+    fn from(notification: NotificationDTO) -> Self {
+        Notification {
+            id: notification.id,
+            key: notification.key,
+            channel: notification.channel,
+            subject: notification.subject,
+            body: notification.body,
+            status: notification.status,
+            attempts: notification.attempts,
+            last_error: notification.last_error,
+            created_at: notification.created_at,
+            sent_at: notification.sent_at,
+        }
+    }
+}
+
+pub(crate) async fn notifications(
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<Notification>> {
+    sqlx::query_as::<_, NotificationDTO>(
+        "SELECT id, key, channel, subject, body, status, attempts, last_error, created_at, sent_at
+        FROM notifications
+        ORDER BY id DESC",
+    )
+    .fetch_all(&appstate.pool)
+    .await
+    .map(|notifications| {
+        notifications
+            .into_iter()
+            .map(Notification::from)
+            .collect::<Vec<Notification>>()
+    })
+    .into()
+}
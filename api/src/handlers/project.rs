@@ -1,13 +1,26 @@
 use axum::extract::{Json as ExtractJson, Path, State};
+use axum_extra::extract::Query;
 use chrono::NaiveDateTime;
+use serde_json::json;
 
-use crate::handlers::work::{workdto_to_work, WorkDTO, WORK_DTO_QUERY};
-use crate::models::{Images, Project, PutProject, Work};
+use crate::error::Error;
+use crate::handlers::search::{reindex, unindex};
+use crate::handlers::tag::{remove_unused_tags, replace_project_tags, TagFilter};
+use crate::handlers::work::{fetch_works, WorkDTO, WORK_DTO_QUERY};
+use crate::models::{ApiResource, Images, Project, PutProject, SearchResource, WebhookEvent, Work};
 use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::webhooks::enqueue;
 use crate::AppState;
 
 static PROJECT_DTO_QUERY: &str = "
-SELECT id, name, description, created_at, header_key, thumbnail_key
+SELECT id, name, description, created_at, header_key, thumbnail_key,
+(
+    SELECT json_group_array(name)
+    FROM (
+        SELECT t.name FROM project_tags pt JOIN tags t ON pt.tag_id = t.id
+        WHERE pt.project_id = projects.id ORDER BY t.name
+    )
+) AS tags
 FROM projects
 ";
 
@@ -18,6 +31,8 @@ struct ProjectDTO {
     description: Option<String>,
     header_key: Option<String>,
     thumbnail_key: Option<String>,
+    /// JSON array of tag names.
+    tags: String,
     created_at: NaiveDateTime,
 }
 
@@ -32,12 +47,27 @@ fn projectdto_to_project(projectdto: ProjectDTO, _appstate: &AppState) -> Projec
         name: projectdto.name,
         description: projectdto.description,
         images,
+        tags: serde_json::from_str(&projectdto.tags).unwrap_or_default(),
         created_at: projectdto.created_at,
     }
 }
 
-pub(crate) async fn projects(State(appstate): State<AppState>) -> JsonResult<Vec<Project>> {
-    sqlx::query_as::<_, ProjectDTO>(PROJECT_DTO_QUERY)
+pub(crate) async fn projects(
+    State(appstate): State<AppState>,
+    Query(filter): Query<TagFilter>,
+) -> JsonResult<Vec<Project>> {
+    let (condition, tags) = filter
+        .condition("id", "project_tags", "project_id")
+        .map(|(condition, tags)| (format!("WHERE {}", condition), tags))
+        .unwrap_or_default();
+
+    let query_string = format!("{} {}", PROJECT_DTO_QUERY, condition);
+    let mut query = sqlx::query_as::<_, ProjectDTO>(&query_string);
+    for tag in tags {
+        query = query.bind(tag);
+    }
+
+    query
         .fetch_all(&appstate.pool)
         .await
         .map(|projects| {
@@ -61,64 +91,130 @@ pub(crate) async fn project(
         .into()
 }
 
+async fn update_project(appstate: &AppState, id: i32, data: &PutProject) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    let updated =
+        sqlx::query("UPDATE projects SET name=?, description=?, thumbnail_key=? WHERE id=?")
+            .bind(&data.name)
+            .bind(&data.description)
+            .bind(&data.thumbnail)
+            .bind(id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+    if updated == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    if let Some(tags) = &data.tags {
+        replace_project_tags(&mut tx, id, tags).await?;
+    }
+    reindex(&mut tx, SearchResource::Project, id).await?;
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::ProjectUpdated,
+        (ApiResource::Project, id).into(),
+        json!({ "name": data.name }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
 pub(crate) async fn put_project(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
     ExtractJson(data): ExtractJson<PutProject>,
 ) -> EmptyResult {
-    sqlx::query("UPDATE projects SET name=?, description=?, thumbnail_key=? WHERE id=?")
-        .bind(data.name)
-        .bind(data.description)
-        .bind(data.thumbnail)
-        .bind(id)
-        .execute(&appstate.pool)
-        .await
-        .into()
+    EmptyResult(update_project(&appstate, id, &data).await)
 }
 
-pub(crate) async fn post_project(
-    State(appstate): State<AppState>,
-    ExtractJson(data): ExtractJson<PutProject>,
-) -> JsonResult<i32> {
-    sqlx::query_scalar(
+async fn insert_project(appstate: &AppState, data: &PutProject) -> Result<i32, sqlx::Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    let id = sqlx::query_scalar(
         "INSERT INTO projects (name, description, thumbnail_key)
         VALUES (?, ?, ?)
         RETURNING id",
     )
-    .bind(data.name)
-    .bind(data.description)
-    .bind(data.thumbnail)
-    .fetch_one(&appstate.pool)
-    .await
-    .into()
+    .bind(&data.name)
+    .bind(&data.description)
+    .bind(&data.thumbnail)
+    .fetch_one(&mut tx)
+    .await?;
+
+    replace_project_tags(&mut tx, id, data.tags.as_deref().unwrap_or_default()).await?;
+    reindex(&mut tx, SearchResource::Project, id).await?;
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::ProjectCreated,
+        (ApiResource::Project, id).into(),
+        json!({ "name": data.name }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_project(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutProject>,
+) -> JsonResult<i32> {
+    insert_project(&appstate, &data).await.into()
 }
 
 pub(crate) async fn works(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
 ) -> JsonResult<Vec<Work>> {
-    sqlx::query_as::<_, WorkDTO>(&format!("{} {}", WORK_DTO_QUERY, "WHERE w.project_id = ?"))
-        .bind(id)
-        .fetch_all(&appstate.pool)
+    let query = format!("{} {}", WORK_DTO_QUERY, "WHERE w.project_id = ?");
+    fetch_works(&appstate, sqlx::query_as::<_, WorkDTO>(&query).bind(id))
         .await
-        .map(|works| {
-            works
-                .into_iter()
-                .map(|w| workdto_to_work(w, &appstate))
-                .collect::<Vec<Work>>()
-        })
         .into()
 }
 
 // DELETE
 
+async fn remove_project(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("DELETE FROM project_tags WHERE project_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    let deleted = sqlx::query("DELETE FROM projects WHERE id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    remove_unused_tags(&mut tx).await?;
+    unindex(&mut tx, SearchResource::Project, id).await?;
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::ProjectDeleted,
+        (ApiResource::Project, id).into(),
+        json!({}),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
 pub(crate) async fn delete_project(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
 ) -> EmptyResult {
-    sqlx::query("DELETE FROM projects WHERE id = ?")
-        .bind(id)
-        .execute(&appstate.pool)
-        .await
-        .into()
+    EmptyResult(remove_project(&appstate, id).await)
 }
@@ -0,0 +1,215 @@
+use axum::extract::{Json as ExtractJson, Path, State};
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::models::{
+    schedule_duration, ControllerProgram, FiringSchedule, PutFiringSchedule, Segment,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct ScheduleDTO {
+    id: i32,
+    name: String,
+    description: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SegmentDTO {
+    schedule_id: i32,
+    ramp_rate: f64,
+    target_temperature: f64,
+    hold_minutes: i32,
+}
+
+async fn fetch_schedules(
+    appstate: &AppState,
+    id: Option<i32>,
+) -> Result<Vec<FiringSchedule>, sqlx::Error> {
+    let schedules = sqlx::query_as::<_, ScheduleDTO>(
+        "SELECT id, name, description
+        FROM firing_schedules
+        WHERE ? IS NULL OR id = ?",
+    )
+    .bind(id)
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let segments = sqlx::query_as::<_, SegmentDTO>(
+        "SELECT schedule_id, ramp_rate, target_temperature, hold_minutes
+        FROM schedule_segments
+        ORDER BY schedule_id, position",
+    )
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let mut by_schedule: HashMap<i32, Vec<Segment>> = HashMap::new();
+    for segment in segments {
+        by_schedule
+            .entry(segment.schedule_id)
+            .or_default()
+            .push(Segment {
+                ramp_rate: segment.ramp_rate,
+                target_temperature: segment.target_temperature,
+                hold_minutes: segment.hold_minutes,
+            });
+    }
+
+    Ok(schedules
+        .into_iter()
+        .map(|s| {
+            let segments = by_schedule.remove(&s.id).unwrap_or_default();
+            FiringSchedule {
+                id: s.id,
+                name: s.name,
+                description: s.description,
+                duration_minutes: schedule_duration(&segments),
+                segments,
+            }
+        })
+        .collect::<Vec<FiringSchedule>>())
+}
+
+async fn fetch_schedule(appstate: &AppState, id: i32) -> Result<Option<FiringSchedule>, Error> {
+    Ok(fetch_schedules(appstate, Some(id))
+        .await?
+        .into_iter()
+        .next())
+}
+
+pub(crate) async fn schedules(State(appstate): State<AppState>) -> JsonResult<Vec<FiringSchedule>> {
+    fetch_schedules(&appstate, None).await.into()
+}
+
+pub(crate) async fn schedule(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<FiringSchedule> {
+    OptionalResult(fetch_schedule(&appstate, id).await)
+}
+
+pub(crate) async fn controller_program(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<ControllerProgram> {
+    OptionalResult(
+        fetch_schedule(&appstate, id)
+            .await
+            .map(|schedule| schedule.as_ref().map(ControllerProgram::from)),
+    )
+}
+
+/// Renders a schedule as a plain text program listing.
+pub(crate) async fn program_listing(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> Result<String, Error> {
+    fetch_schedule(&appstate, id)
+        .await?
+        .map(|schedule| ControllerProgram::from(&schedule).to_string())
+        .ok_or(Error::ResourceNotFound)
+}
+
+async fn insert_segments(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    id: i32,
+    segments: &[Segment],
+) -> Result<(), Error> {
+    if let Some(position) = segments.iter().position(|s| !s.is_valid()) {
+        return Err(Error::InvalidSegment(position + 1));
+    }
+
+    for (position, segment) in segments.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO schedule_segments (schedule_id, position, ramp_rate, target_temperature, hold_minutes)
+            VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(position as i32)
+        .bind(segment.ramp_rate)
+        .bind(segment.target_temperature)
+        .bind(segment.hold_minutes)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// PUT
+
+async fn update_schedule(
+    appstate: &AppState,
+    id: i32,
+    data: &PutFiringSchedule,
+) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("UPDATE firing_schedules SET name=?, description=? WHERE id=?")
+        .bind(&data.name)
+        .bind(&data.description)
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    sqlx::query("DELETE FROM schedule_segments WHERE schedule_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    insert_segments(&mut tx, id, &data.segments).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn put_schedule(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutFiringSchedule>,
+) -> EmptyResult {
+    update_schedule(&appstate, id, &data).await.into()
+}
+
+// POST
+
+async fn insert_schedule(appstate: &AppState, data: &PutFiringSchedule) -> Result<i32, Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO firing_schedules (name, description)
+        VALUES (?, ?)
+        RETURNING id",
+    )
+    .bind(&data.name)
+    .bind(&data.description)
+    .fetch_one(&mut tx)
+    .await?;
+
+    insert_segments(&mut tx, id, &data.segments).await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_schedule(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutFiringSchedule>,
+) -> JsonResult<i32> {
+    insert_schedule(&appstate, &data).await.into()
+}
+
+// DELETE
+
+pub(crate) async fn delete_schedule(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    sqlx::query("DELETE FROM firing_schedules WHERE id = ?")
+        .bind(id)
+        .execute(&appstate.pool)
+        .await
+        .into()
+}
@@ -0,0 +1,128 @@
+use axum::extract::{Query, State};
+use serde::Deserialize;
+use sqlx::{Executor, Sqlite, Transaction};
+
+use crate::models::{
+    highlight_html, search_query, ApiResource, SearchResource, SearchResult, MATCH_END, MATCH_START,
+};
+use crate::result::JsonResult;
+use crate::AppState;
+
+/// Most results returned by a search.
+const MAX_RESULTS: i64 = 100;
+
+/// Removes a resource from the search index.
+pub(crate) async fn unindex<'c, E>(
+    executor: E,
+    resource: SearchResource,
+    id: i32,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    sqlx::query("DELETE FROM search_index WHERE resource = ? AND resource_id = ?")
+        .bind(resource)
+        .bind(id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Brings the search index up to date with a resource, after it has been
+/// inserted or updated within the transaction.
+pub(crate) async fn reindex(
+    tx: &mut Transaction<'_, Sqlite>,
+    resource: SearchResource,
+    id: i32,
+) -> Result<(), sqlx::Error> {
+    unindex(&mut *tx, resource, id).await?;
+
+    let select = match resource {
+        SearchResource::Work => "SELECT ?, id, name, notes, glaze_description FROM works",
+        SearchResource::Project => "SELECT ?, id, name, description, NULL FROM projects",
+        SearchResource::Glaze => "SELECT ?, id, name, description, NULL FROM glazes",
+    };
+    sqlx::query(&format!(
+        "INSERT INTO search_index (resource, resource_id, name, description, glaze_description)
+        {} WHERE id = ?",
+        select
+    ))
+    .bind(resource)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    q: String,
+    limit: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SearchResultDTO {
+    resource: SearchResource,
+    resource_id: i32,
+    name: String,
+    snippet: String,
+    rank: f64,
+}
+
+impl From<SearchResultDTO> for SearchResult {
+    fn from(result: SearchResultDTO) -> Self {
+        let resource = match result.resource {
+            SearchResource::Work => ApiResource::Work,
+            SearchResource::Project => ApiResource::Project,
+            SearchResource::Glaze => ApiResource::Glaze,
+        };
+
+        SearchResult {
+            resource: result.resource,
+            reference: (resource, result.resource_id).into(),
+            name: highlight_html(&result.name),
+            snippet: highlight_html(&result.snippet),
+            score: -result.rank,
+        }
+    }
+}
+
+/// Works, projects and glazes containing every word searched for, the best
+/// matches first. Matches in names count for more than those elsewhere.
+pub(crate) async fn search(
+    State(appstate): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> JsonResult<Vec<SearchResult>> {
+    let query = match search_query(&params.q) {
+        Some(query) => query,
+        None => return JsonResult(Ok(Vec::new())),
+    };
+
+    sqlx::query_as::<_, SearchResultDTO>(
+        "SELECT resource, resource_id,
+        highlight(search_index, 2, ?, ?) AS name,
+        snippet(search_index, -1, ?, ?, '…', 16) AS snippet,
+        bm25(search_index, 0.0, 0.0, 10.0, 4.0, 4.0) AS rank
+        FROM search_index
+        WHERE search_index MATCH ?
+        ORDER BY rank
+        LIMIT ?",
+    )
+    .bind(MATCH_START.to_string())
+    .bind(MATCH_END.to_string())
+    .bind(MATCH_START.to_string())
+    .bind(MATCH_END.to_string())
+    .bind(query)
+    .bind(params.limit.unwrap_or(20).clamp(1, MAX_RESULTS))
+    .fetch_all(&appstate.pool)
+    .await
+    .map(|results| {
+        results
+            .into_iter()
+            .map(SearchResult::from)
+            .collect::<Vec<SearchResult>>()
+    })
+    .into()
+}
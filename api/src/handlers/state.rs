@@ -0,0 +1,414 @@
+use axum::extract::{Json as ExtractJson, Path, State};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+
+use crate::error::Error;
+use crate::models::{
+    is_valid_max_days, PutStateDefinition, PutWorkflow, State as WorkState, StateDefinition,
+    StateMachine, StateTransition, Workflow,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct StateDTO {
+    id: i32,
+    key: String,
+    name: String,
+    max_days: Option<f64>,
+}
+
+impl From<StateDTO> for StateDefinition {
+    fn from(state: StateDTO) -> Self {
+        let key = WorkState::from(state.key);
+        StateDefinition {
+            id: state.id,
+            is_built_in: key.is_built_in(),
+            key,
+            name: state.name,
+            max_days: state.max_days,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct WorkflowDTO {
+    id: i32,
+    key: String,
+    name: String,
+    description: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct StateTransitionDTO {
+    workflow_id: i32,
+    from_state: String,
+    to_state: String,
+}
+
+pub(crate) async fn load_state_machine(pool: &SqlitePool) -> Result<StateMachine, sqlx::Error> {
+    let states =
+        sqlx::query_as::<_, StateDTO>("SELECT id, key, name, max_days FROM states ORDER BY id")
+            .fetch_all(pool)
+            .await?;
+
+    let workflows = sqlx::query_as::<_, WorkflowDTO>(
+        "SELECT id, key, name, description FROM workflows ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let transitions = sqlx::query_as::<_, StateTransitionDTO>(
+        "SELECT st.workflow_id, f.key AS from_state, t.key AS to_state
+        FROM state_transitions st
+        JOIN states f ON st.from_state = f.id
+        JOIN states t ON st.to_state = t.id
+        ORDER BY st.workflow_id, f.id, t.id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(StateMachine::new(
+        states.into_iter().map(StateDefinition::from).collect(),
+        workflows
+            .into_iter()
+            .map(|w| Workflow {
+                id: w.id,
+                transitions: transitions
+                    .iter()
+                    .filter(|t| t.workflow_id == w.id)
+                    .map(|t| StateTransition {
+                        from: t.from_state.clone().into(),
+                        to: t.to_state.clone().into(),
+                    })
+                    .collect(),
+                key: w.key,
+                name: w.name,
+                description: w.description,
+            })
+            .collect(),
+    ))
+}
+
+/// Reloads the state machine after its states or workflows are edited.
+async fn reload(appstate: &AppState) -> Result<(), Error> {
+    let machine = load_state_machine(&appstate.pool).await?;
+    *appstate.states.write().await = machine;
+    Ok(())
+}
+
+pub(crate) async fn states(State(appstate): State<AppState>) -> JsonResult<Vec<StateDefinition>> {
+    JsonResult(Ok(appstate.states.read().await.states().to_vec()))
+}
+
+pub(crate) async fn workflows(State(appstate): State<AppState>) -> JsonResult<Vec<Workflow>> {
+    JsonResult(Ok(appstate.states.read().await.workflows().to_vec()))
+}
+
+pub(crate) async fn workflow(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<Workflow> {
+    OptionalResult(Ok(appstate
+        .states
+        .read()
+        .await
+        .workflows()
+        .iter()
+        .find(|w| w.id == id)
+        .cloned()))
+}
+
+/// Replaces the transitions of a workflow within a transaction.
+async fn replace_transitions(
+    tx: &mut Transaction<'_, Sqlite>,
+    machine: &StateMachine,
+    workflow_id: i32,
+    transitions: &[StateTransition],
+) -> Result<(), Error> {
+    let mut ids = Vec::new();
+    for transition in transitions {
+        for state in [&transition.from, &transition.to] {
+            if machine.id(state).is_none() {
+                return Err(Error::UnknownState(state.key().to_string()));
+            }
+        }
+        ids.push((machine.id(&transition.from), machine.id(&transition.to)));
+    }
+
+    sqlx::query("DELETE FROM state_transitions WHERE workflow_id = ?")
+        .bind(workflow_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for (from_state, to_state) in ids {
+        sqlx::query(
+            "INSERT OR IGNORE INTO state_transitions (workflow_id, from_state, to_state)
+            VALUES (?, ?, ?)",
+        )
+        .bind(workflow_id)
+        .bind(from_state)
+        .bind(to_state)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// PUT
+
+async fn update_state(
+    appstate: &AppState,
+    id: i32,
+    data: &PutStateDefinition,
+) -> Result<(), Error> {
+    if !data.max_days.is_none_or(is_valid_max_days) {
+        return Err(Error::InvalidMaxDays);
+    }
+
+    let machine = appstate.states.read().await.clone();
+    let existing = machine
+        .states()
+        .iter()
+        .find(|s| s.id == id)
+        .ok_or(Error::ResourceNotFound)?;
+
+    if existing.key != data.key {
+        if existing.is_built_in {
+            return Err(Error::BuiltInState);
+        }
+        if machine.id(&data.key).is_some() {
+            return Err(Error::StateExists);
+        }
+    }
+
+    sqlx::query("UPDATE states SET key=?, name=?, max_days=? WHERE id=?")
+        .bind(data.key.key())
+        .bind(&data.name)
+        .bind(data.max_days)
+        .bind(id)
+        .execute(&appstate.pool)
+        .await?;
+
+    reload(appstate).await
+}
+
+pub(crate) async fn put_state(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutStateDefinition>,
+) -> EmptyResult {
+    EmptyResult(update_state(&appstate, id, &data).await)
+}
+
+async fn update_workflow(appstate: &AppState, id: i32, data: &PutWorkflow) -> Result<(), Error> {
+    let machine = appstate.states.read().await.clone();
+    let existing = machine
+        .workflows()
+        .iter()
+        .find(|w| w.id == id)
+        .ok_or(Error::ResourceNotFound)?;
+
+    if existing.key != data.key {
+        if existing.key == StateMachine::DEFAULT_WORKFLOW {
+            return Err(Error::DefaultWorkflow);
+        }
+        if machine.workflow(&data.key).is_some() {
+            return Err(Error::WorkflowExists);
+        }
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("UPDATE workflows SET key=?, name=?, description=? WHERE id=?")
+        .bind(&data.key)
+        .bind(&data.name)
+        .bind(&data.description)
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    replace_transitions(&mut tx, &machine, id, &data.transitions).await?;
+
+    tx.commit().await?;
+    reload(appstate).await
+}
+
+pub(crate) async fn put_workflow(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutWorkflow>,
+) -> EmptyResult {
+    EmptyResult(update_workflow(&appstate, id, &data).await)
+}
+
+// POST
+
+async fn insert_state(appstate: &AppState, data: &PutStateDefinition) -> Result<i32, Error> {
+    if !data.max_days.is_none_or(is_valid_max_days) {
+        return Err(Error::InvalidMaxDays);
+    }
+    if appstate.states.read().await.id(&data.key).is_some() {
+        return Err(Error::StateExists);
+    }
+
+    let id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO states (key, name, max_days)
+        VALUES (?, ?, ?)
+        RETURNING id",
+    )
+    .bind(data.key.key())
+    .bind(&data.name)
+    .bind(data.max_days)
+    .fetch_one(&appstate.pool)
+    .await?;
+
+    reload(appstate).await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_state(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutStateDefinition>,
+) -> JsonResult<i32> {
+    JsonResult(insert_state(&appstate, &data).await)
+}
+
+async fn insert_workflow(appstate: &AppState, data: &PutWorkflow) -> Result<i32, Error> {
+    let machine = appstate.states.read().await.clone();
+    if machine.workflow(&data.key).is_some() {
+        return Err(Error::WorkflowExists);
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    let id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO workflows (key, name, description)
+        VALUES (?, ?, ?)
+        RETURNING id",
+    )
+    .bind(&data.key)
+    .bind(&data.name)
+    .bind(&data.description)
+    .fetch_one(&mut tx)
+    .await?;
+
+    replace_transitions(&mut tx, &machine, id, &data.transitions).await?;
+
+    tx.commit().await?;
+    reload(appstate).await?;
+    Ok(id)
+}
+
+pub(crate) async fn post_workflow(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutWorkflow>,
+) -> JsonResult<i32> {
+    JsonResult(insert_workflow(&appstate, &data).await)
+}
+
+// DELETE
+
+/// Removes a state and its transitions, provided it is neither built in nor
+/// referenced by any event.
+async fn remove_state(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let is_built_in = appstate
+        .states
+        .read()
+        .await
+        .states()
+        .iter()
+        .find(|s| s.id == id)
+        .ok_or(Error::ResourceNotFound)?
+        .is_built_in;
+    if is_built_in {
+        return Err(Error::BuiltInState);
+    }
+
+    let events = sqlx::query_scalar::<_, i32>(
+        "SELECT COUNT(*) FROM events WHERE previous_state = ? OR current_state = ?",
+    )
+    .bind(id)
+    .bind(id)
+    .fetch_one(&appstate.pool)
+    .await?;
+    if events > 0 {
+        return Err(Error::StateInUse);
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("DELETE FROM state_transitions WHERE from_state = ? OR to_state = ?")
+        .bind(id)
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    sqlx::query("DELETE FROM clay_state_thresholds WHERE state_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    sqlx::query("DELETE FROM states WHERE id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+    reload(appstate).await
+}
+
+pub(crate) async fn delete_state(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(remove_state(&appstate, id).await)
+}
+
+/// Removes a workflow and its transitions, provided no works follow it. The
+/// default workflow is always kept.
+async fn remove_workflow(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let key = appstate
+        .states
+        .read()
+        .await
+        .workflows()
+        .iter()
+        .find(|w| w.id == id)
+        .ok_or(Error::ResourceNotFound)?
+        .key
+        .clone();
+    if key == StateMachine::DEFAULT_WORKFLOW {
+        return Err(Error::DefaultWorkflow);
+    }
+
+    let works = sqlx::query_scalar::<_, i32>("SELECT COUNT(*) FROM works WHERE workflow_id = ?")
+        .bind(id)
+        .fetch_one(&appstate.pool)
+        .await?;
+    if works > 0 {
+        return Err(Error::WorkflowInUse);
+    }
+
+    let mut tx = appstate.pool.begin().await?;
+
+    sqlx::query("DELETE FROM state_transitions WHERE workflow_id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    sqlx::query("DELETE FROM workflows WHERE id = ?")
+        .bind(id)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+    reload(appstate).await
+}
+
+pub(crate) async fn delete_workflow(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(remove_workflow(&appstate, id).await)
+}
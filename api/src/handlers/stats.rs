@@ -0,0 +1,271 @@
+use axum::extract::State;
+use sqlx::SqlitePool;
+
+use crate::models::{
+    ApiResource, ApiResourceReference, Defect, DefectCount, DefectGroup, DefectStats,
+    State as WorkState,
+};
+use crate::result::JsonResult;
+use crate::AppState;
+
+#[derive(sqlx::FromRow)]
+struct DefectRowDTO {
+    id: Option<i32>,
+    name: Option<String>,
+    defect: Option<Defect>,
+    count: i32,
+}
+
+/// Counts the failures of each defect within each group, given the columns
+/// identifying and naming a group, the joins they need and the column to
+/// group by. Without a column to group by, failures are counted across
+/// every work.
+async fn fetch_defect_rows(
+    pool: &SqlitePool,
+    select: &str,
+    joins: &str,
+    group: Option<&str>,
+) -> Result<Vec<DefectRowDTO>, sqlx::Error> {
+    let columns = match group {
+        Some(group) => format!("{}, e.defect", group),
+        None => "e.defect".to_string(),
+    };
+    let query = format!(
+        "SELECT {}, e.defect, COUNT(DISTINCT e.id) AS count
+        FROM events e
+        JOIN works w ON e.work_id = w.id
+        {}
+        WHERE e.current_state IN (SELECT id FROM states WHERE key IN (?, ?))
+        GROUP BY {}
+        ORDER BY {}",
+        select, joins, columns, columns
+    );
+
+    sqlx::query_as::<_, DefectRowDTO>(&query)
+        .bind(WorkState::RECYCLED.key())
+        .bind(WorkState::FAILED_IN_FIRING.key())
+        .fetch_all(pool)
+        .await
+}
+
+/// Folds rows ordered by group into groups, the largest first.
+fn group_rows(
+    rows: Vec<DefectRowDTO>,
+    resource: impl Fn(i32) -> Option<ApiResourceReference>,
+) -> Vec<DefectGroup> {
+    let mut groups: Vec<(Option<i32>, DefectGroup)> = Vec::new();
+    for row in rows {
+        if groups.last().is_none_or(|(id, _)| *id != row.id) {
+            groups.push((
+                row.id,
+                DefectGroup {
+                    resource: row.id.and_then(&resource),
+                    name: row.name.unwrap_or_default(),
+                    failures: 0,
+                    defects: Vec::new(),
+                },
+            ));
+        }
+        if let Some((_, group)) = groups.last_mut() {
+            group.failures += row.count;
+            group.defects.push(DefectCount {
+                defect: row.defect,
+                count: row.count,
+            });
+        }
+    }
+
+    let mut groups = groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .collect::<Vec<DefectGroup>>();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.failures));
+    groups
+}
+
+async fn fetch_defect_stats(pool: &SqlitePool) -> Result<DefectStats, sqlx::Error> {
+    let defects = fetch_defect_rows(pool, "NULL AS id, NULL AS name", "", None)
+        .await?
+        .into_iter()
+        .map(|row| DefectCount {
+            defect: row.defect,
+            count: row.count,
+        })
+        .collect::<Vec<DefectCount>>();
+
+    let clays = fetch_defect_rows(
+        pool,
+        "c.id, c.name",
+        "JOIN clays c ON w.clay_id = c.id",
+        Some("c.id"),
+    )
+    .await?;
+
+    let glazes = fetch_defect_rows(
+        pool,
+        "g.id, g.name",
+        "JOIN work_glazes wg ON wg.work_id = w.id JOIN glazes g ON wg.glaze_id = g.id",
+        Some("g.id"),
+    )
+    .await?;
+
+    let states = fetch_defect_rows(
+        pool,
+        "s.id, s.key AS name",
+        "JOIN states s ON e.previous_state = s.id",
+        Some("s.id"),
+    )
+    .await?;
+
+    let firings = fetch_defect_rows(
+        pool,
+        "f.id, f.firing_type || ' firing in ' || k.name AS name",
+        "JOIN firings f ON e.firing_id = f.id JOIN kilns k ON f.kiln_id = k.id",
+        Some("f.id"),
+    )
+    .await?;
+
+    Ok(DefectStats {
+        failures: defects.iter().map(|d| d.count).sum(),
+        defects,
+        clays: group_rows(clays, |id| Some((ApiResource::Clay, id).into())),
+        glazes: group_rows(glazes, |id| Some((ApiResource::Glaze, id).into())),
+        states: group_rows(states, |_| None),
+        firings: group_rows(firings, |id| Some((ApiResource::Firing, id).into())),
+    })
+}
+
+pub(crate) async fn defects(State(appstate): State<AppState>) -> JsonResult<DefectStats> {
+    fetch_defect_stats(&appstate.pool).await.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Defect;
+
+    fn row(id: i32, name: &str, defect: Option<Defect>, count: i32) -> DefectRowDTO {
+        DefectRowDTO {
+            id: Some(id),
+            name: Some(name.to_string()),
+            defect,
+            count,
+        }
+    }
+
+    #[test]
+    fn test_group_rows() {
+        let rows = vec![
+            row(1, "White", Some(Defect::SCrack), 1),
+            row(2, "Red", None, 2),
+            row(2, "Red", Some(Defect::Warping), 3),
+            row(3, "Black", Some(Defect::BlowOut), 1),
+        ];
+        let groups = group_rows(rows, |id| Some((ApiResource::Clay, id).into()));
+
+        // The largest group comes first, and ties keep the order of the rows.
+        assert_eq!(
+            groups
+                .iter()
+                .map(|g| (g.name.as_str(), g.failures))
+                .collect::<Vec<(&str, i32)>>(),
+            vec![("Red", 5), ("White", 1), ("Black", 1)]
+        );
+        assert_eq!(groups[0].resource.as_ref().unwrap().url, "/clays/2");
+        assert_eq!(
+            groups[0]
+                .defects
+                .iter()
+                .map(|d| (d.defect, d.count))
+                .collect::<Vec<(Option<Defect>, i32)>>(),
+            vec![(None, 2), (Some(Defect::Warping), 3)]
+        );
+
+        assert!(group_rows(Vec::new(), |_| None).is_empty());
+    }
+
+    async fn record_failure(
+        pool: &SqlitePool,
+        clay_id: i32,
+        previous: &str,
+        current: &str,
+        defect: Defect,
+    ) {
+        let work_id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO works (project_id, name, clay_id) VALUES (1, 'Bowl', ?) RETURNING id",
+        )
+        .bind(clay_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "INSERT INTO events (work_id, previous_state, current_state, defect)
+            VALUES (?, (SELECT id FROM states WHERE key = ?), (SELECT id FROM states WHERE key = ?), ?)",
+        )
+        .bind(work_id)
+        .bind(previous)
+        .bind(current)
+        .bind(defect)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_defect_stats() {
+        let pool = crate::test_pool().await;
+        sqlx::query("INSERT INTO projects (id, name) VALUES (1, 'Bowls')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        record_failure(&pool, 1, "Thrown", "Recycled", Defect::SCrack).await;
+        record_failure(
+            &pool,
+            3,
+            "AwaitingBisqueFiring",
+            "FailedInFiring",
+            Defect::BlowOut,
+        )
+        .await;
+
+        // Transitions other than failures are not counted.
+        sqlx::query(
+            "INSERT INTO events (work_id, previous_state, current_state)
+            VALUES (1, (SELECT id FROM states WHERE key = 'Thrown'), (SELECT id FROM states WHERE key = 'Trimming'))",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let stats = fetch_defect_stats(&pool).await.unwrap();
+        assert_eq!(stats.failures, 2);
+        assert_eq!(
+            stats
+                .defects
+                .iter()
+                .map(|d| (d.defect, d.count))
+                .collect::<Vec<(Option<Defect>, i32)>>(),
+            vec![(Some(Defect::BlowOut), 1), (Some(Defect::SCrack), 1)]
+        );
+        assert_eq!(
+            stats
+                .clays
+                .iter()
+                .map(|g| (g.name.as_str(), g.failures))
+                .collect::<Vec<(&str, i32)>>(),
+            vec![("White", 1), ("Red", 1)]
+        );
+        assert_eq!(
+            stats
+                .states
+                .iter()
+                .map(|g| g.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["Thrown", "AwaitingBisqueFiring"]
+        );
+        assert!(stats.glazes.is_empty());
+        assert!(stats.firings.is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+use axum::extract::State;
+use serde::Deserialize;
+use sqlx::{Executor, Sqlite, Transaction};
+
+use crate::models::{normalise_tags, TagMatch, TagUsage};
+use crate::result::JsonResult;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct TagFilter {
+    #[serde(default)]
+    pub(crate) tag: Vec<String>,
+    #[serde(default, rename = "match")]
+    pub(crate) tag_match: TagMatch,
+}
+
+impl TagFilter {
+    /// Condition limiting `column` to the ids of resources with the tags
+    /// filtered by, given the table linking resources to their tags and its
+    /// column referencing them, along with the tags to bind to it. None if
+    /// there are no tags to filter by.
+    pub(crate) fn condition(
+        &self,
+        column: &str,
+        table: &str,
+        table_column: &str,
+    ) -> Option<(String, Vec<String>)> {
+        let tags = normalise_tags(&self.tag);
+        if tags.is_empty() {
+            return None;
+        }
+
+        let having = match self.tag_match {
+            TagMatch::All => format!("HAVING COUNT(DISTINCT t.id) = {}", tags.len()),
+            TagMatch::Any => String::new(),
+        };
+        let condition = format!(
+            "{} IN (
+                SELECT rt.{} FROM {} rt JOIN tags t ON rt.tag_id = t.id
+                WHERE t.name IN ({})
+                GROUP BY rt.{} {}
+            )",
+            column,
+            table_column,
+            table,
+            vec!["?"; tags.len()].join(", "),
+            table_column,
+            having
+        );
+
+        Some((condition, tags))
+    }
+}
+
+/// Removes tags that are no longer on any work or project.
+pub(crate) async fn remove_unused_tags<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    sqlx::query(
+        "DELETE FROM tags
+        WHERE id NOT IN (SELECT tag_id FROM work_tags)
+        AND id NOT IN (SELECT tag_id FROM project_tags)",
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces the tags of a work or project, given the table linking it to its
+/// tags and the column of that table referencing it.
+async fn replace_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    table: &str,
+    column: &str,
+    id: i32,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DELETE FROM {} WHERE {} = ?", table, column))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    for tag in normalise_tags(tags) {
+        sqlx::query("INSERT OR IGNORE INTO tags (name) VALUES (?)")
+            .bind(&tag)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(&format!(
+            "INSERT INTO {} ({}, tag_id) SELECT ?, id FROM tags WHERE name = ?",
+            table, column
+        ))
+        .bind(id)
+        .bind(&tag)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    remove_unused_tags(&mut *tx).await
+}
+
+pub(crate) async fn replace_work_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: i32,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    replace_tags(tx, "work_tags", "work_id", id, tags).await
+}
+
+pub(crate) async fn replace_project_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: i32,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    replace_tags(tx, "project_tags", "project_id", id, tags).await
+}
+
+#[derive(sqlx::FromRow)]
+struct TagUsageDTO {
+    name: String,
+    works: i32,
+    projects: i32,
+}
+
+/// Tags in use, the most used first.
+pub(crate) async fn tags(State(appstate): State<AppState>) -> JsonResult<Vec<TagUsage>> {
+    sqlx::query_as::<_, TagUsageDTO>(
+        "SELECT t.name,
+        (SELECT COUNT(*) FROM work_tags WHERE tag_id = t.id) AS works,
+        (SELECT COUNT(*) FROM project_tags WHERE tag_id = t.id) AS projects
+        FROM tags t
+        ORDER BY works + projects DESC, t.name",
+    )
+    .fetch_all(&appstate.pool)
+    .await
+    .map(|tags| {
+        tags.into_iter()
+            .map(|t| TagUsage {
+                name: t.name,
+                works: t.works,
+                projects: t.projects,
+            })
+            .collect::<Vec<TagUsage>>()
+    })
+    .into()
+}
@@ -0,0 +1,261 @@
+use axum::extract::{Json as ExtractJson, Path, State};
+use chrono::NaiveDateTime;
+
+use crate::error::Error;
+use crate::models::{
+    PutWebhookEndpoint, WebhookDelivery, WebhookDeliveryStatus, WebhookEndpoint, WebhookEvent,
+};
+use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::AppState;
+
+static WEBHOOK_DTO_QUERY: &str = "
+SELECT id, url, description, is_active, created_at
+FROM webhook_endpoints
+";
+
+#[derive(sqlx::FromRow)]
+struct WebhookEndpointDTO {
+    id: i32,
+    url: String,
+    description: Option<String>,
+    is_active: bool,
+    created_at: NaiveDateTime,
+}
+
+impl From<WebhookEndpointDTO> for WebhookEndpoint {
+    fn from(endpoint: WebhookEndpointDTO) -> Self {
+        WebhookEndpoint {
+            id: endpoint.id,
+            url: endpoint.url,
+            description: endpoint.description,
+            is_active: endpoint.is_active,
+            created_at: endpoint.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct WebhookDeliveryDTO {
+    id: i32,
+    event: WebhookEvent,
+    payload: String,
+    status: WebhookDeliveryStatus,
+    attempts: i32,
+    next_attempt_at: NaiveDateTime,
+    response_status: Option<i32>,
+    last_error: Option<String>,
+    created_at: NaiveDateTime,
+    delivered_at: Option<NaiveDateTime>,
+}
+
+impl From<WebhookDeliveryDTO> for WebhookDelivery {
+    fn from(delivery: WebhookDeliveryDTO) -> Self {
+        WebhookDelivery {
+            id: delivery.id,
+            event: delivery.event,
+            payload: serde_json::from_str(&delivery.payload).unwrap_or_default(),
+            status: delivery.status,
+            attempts: delivery.attempts,
+            next_attempt_at: (delivery.status == WebhookDeliveryStatus::Pending)
+                .then_some(delivery.next_attempt_at),
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
+
+fn is_valid_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https")
+}
+
+fn endpoint_error(data: &PutWebhookEndpoint) -> Option<Error> {
+    if !is_valid_url(&data.url) {
+        return Some(Error::InvalidWebhookUrl);
+    }
+    // Signing with an empty key would let anyone forge a signature.
+    if data.secret.trim().is_empty() {
+        return Some(Error::InvalidWebhookSecret);
+    }
+    None
+}
+
+pub(crate) async fn webhooks(State(appstate): State<AppState>) -> JsonResult<Vec<WebhookEndpoint>> {
+    sqlx::query_as::<_, WebhookEndpointDTO>(WEBHOOK_DTO_QUERY)
+        .fetch_all(&appstate.pool)
+        .await
+        .map(|endpoints| {
+            endpoints
+                .into_iter()
+                .map(WebhookEndpoint::from)
+                .collect::<Vec<WebhookEndpoint>>()
+        })
+        .into()
+}
+
+pub(crate) async fn webhook(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<WebhookEndpoint> {
+    sqlx::query_as::<_, WebhookEndpointDTO>(&format!("{} {}", WEBHOOK_DTO_QUERY, "WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await
+        .map(|opt_endpoint| opt_endpoint.map(WebhookEndpoint::from))
+        .into()
+}
+
+async fn fetch_deliveries(appstate: &AppState, id: i32) -> Result<Vec<WebhookDelivery>, Error> {
+    sqlx::query_scalar::<_, i32>("SELECT id FROM webhook_endpoints WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&appstate.pool)
+        .await?
+        .ok_or(Error::ResourceNotFound)?;
+
+    let deliveries = sqlx::query_as::<_, WebhookDeliveryDTO>(
+        "SELECT id, event, payload, status, attempts, next_attempt_at, response_status,
+        last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE endpoint_id = ?
+        ORDER BY id DESC",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    Ok(deliveries
+        .into_iter()
+        .map(WebhookDelivery::from)
+        .collect::<Vec<WebhookDelivery>>())
+}
+
+/// The calls made to an endpoint, the latest first.
+pub(crate) async fn deliveries(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<WebhookDelivery>> {
+    JsonResult(fetch_deliveries(&appstate, id).await)
+}
+
+// PUT
+
+async fn update_webhook(
+    appstate: &AppState,
+    id: i32,
+    data: &PutWebhookEndpoint,
+) -> Result<(), Error> {
+    if let Some(e) = endpoint_error(data) {
+        return Err(e);
+    }
+
+    let updated = sqlx::query(
+        "UPDATE webhook_endpoints SET url=?, secret=?, description=?, is_active=? WHERE id=?",
+    )
+    .bind(&data.url)
+    .bind(&data.secret)
+    .bind(&data.description)
+    .bind(data.is_active)
+    .bind(id)
+    .execute(&appstate.pool)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn put_webhook(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutWebhookEndpoint>,
+) -> EmptyResult {
+    EmptyResult(update_webhook(&appstate, id, &data).await)
+}
+
+/// Schedules a delivery that has been given up on to be attempted again.
+async fn reschedule_delivery(appstate: &AppState, id: i32, delivery_id: i32) -> Result<(), Error> {
+    let status = sqlx::query_scalar::<_, WebhookDeliveryStatus>(
+        "SELECT status FROM webhook_deliveries WHERE id = ? AND endpoint_id = ?",
+    )
+    .bind(delivery_id)
+    .bind(id)
+    .fetch_optional(&appstate.pool)
+    .await?
+    .ok_or(Error::ResourceNotFound)?;
+    if status != WebhookDeliveryStatus::Failed {
+        return Err(Error::DeliveryNotFailed);
+    }
+
+    sqlx::query(
+        "UPDATE webhook_deliveries
+        SET status = 'Pending', attempts = 0,
+        next_attempt_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+        WHERE id = ?",
+    )
+    .bind(delivery_id)
+    .execute(&appstate.pool)
+    .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn retry_delivery(
+    Path((id, delivery_id)): Path<(i32, i32)>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(reschedule_delivery(&appstate, id, delivery_id).await)
+}
+
+// POST
+
+async fn insert_webhook(appstate: &AppState, data: &PutWebhookEndpoint) -> Result<i32, Error> {
+    if let Some(e) = endpoint_error(data) {
+        return Err(e);
+    }
+
+    let id = sqlx::query_scalar(
+        "INSERT INTO webhook_endpoints (url, secret, description, is_active)
+        VALUES (?, ?, ?, ?)
+        RETURNING id",
+    )
+    .bind(&data.url)
+    .bind(&data.secret)
+    .bind(&data.description)
+    .bind(data.is_active)
+    .fetch_one(&appstate.pool)
+    .await?;
+
+    Ok(id)
+}
+
+pub(crate) async fn post_webhook(
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutWebhookEndpoint>,
+) -> JsonResult<i32> {
+    JsonResult(insert_webhook(&appstate, &data).await)
+}
+
+// DELETE
+
+async fn remove_webhook(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let deleted = sqlx::query("DELETE FROM webhook_endpoints WHERE id = ?")
+        .bind(id)
+        .execute(&appstate.pool)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn delete_webhook(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(remove_webhook(&appstate, id).await)
+}
@@ -1,31 +1,67 @@
 use axum::extract::{Json as ExtractJson, Path, State};
-use axum::response::IntoResponse;
-use chrono::NaiveDateTime;
+use axum_extra::extract::Query;
+use chrono::{NaiveDateTime, Utc};
 use serde::Serialize;
+use serde_json::json;
+use sqlx::query::QueryAs;
+use sqlx::sqlite::{Sqlite, SqliteArguments};
+use sqlx::Transaction;
+use std::collections::HashMap;
 
 use crate::error::{internal_error, Error};
+use crate::handlers::event::{notify_events, EventDTO, EVENT_DTO_QUERY};
+use crate::handlers::glaze::glaze_layers;
+use crate::handlers::material::recipe_umf;
+use crate::handlers::search::{reindex, unindex};
+use crate::handlers::tag::{remove_unused_tags, replace_work_tags, TagFilter};
 use crate::models::{
-    is_valid_transition, ApiResource, Clay, CurrentState, Event, Images, PostWork, PutWork,
-    State as WorkState, Work,
+    glaze_fit, staleness, work_shrinkage, ApiResource, Clay, CurrentState, Event, GlazeChemistry,
+    GlazeLayer, Images, MeasuredShrinkage, PostWork, PutGlazeLayer, PutState, PutWork, Recipe,
+    RevertedEvent, SearchResource, StaleWork, State as WorkState, StateMachine, Transition,
+    WebhookEvent, Work,
 };
 use crate::result::{EmptyResult, JsonResult, OptionalResult};
+use crate::webhooks::enqueue;
 use crate::AppState;
 
+/// Format timestamps are stored in by the database, so that those given in
+/// requests sort alongside them.
+pub(crate) static TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f";
+
 pub(crate) static WORK_DTO_QUERY: &str = "
-SELECT w.id, w.project_id, w.name, w.notes, w.glaze_description, w.created_at, w.header_key, w.thumbnail_key, w.is_multiple,
-e.current_state_id, e.current_state_transitioned,
-c.id as clay_id, c.name as clay_name, c.description as clay_description, c.shrinkage as clay_shrinkage
+SELECT w.id, w.project_id, w.name, w.notes, w.glaze_description, w.glaze_recipe, w.glaze_umf, w.created_at, w.header_key, w.thumbnail_key, w.is_multiple,
+w.footprint_width_cm, w.footprint_depth_cm, w.height_cm, wf.key as workflow,
+(
+    SELECT COUNT(*)
+    FROM events fe
+    WHERE fe.work_id = w.id
+    AND fe.previous_state IN (SELECT id FROM states WHERE key IN ('AwaitingBisqueFiring', 'AwaitingGlazeFiring'))
+    AND fe.current_state NOT IN (SELECT id FROM states WHERE key = 'Recycled')
+) as firing_count,
+(
+    SELECT json_group_array(name)
+    FROM (
+        SELECT t.name FROM work_tags wt JOIN tags t ON wt.tag_id = t.id
+        WHERE wt.work_id = w.id ORDER BY t.name
+    )
+) as tags,
+e.current_state, e.current_state_transitioned,
+c.id as clay_id, c.name as clay_name, c.description as clay_description, c.shrinkage as clay_shrinkage,
+c.coe as clay_coe, c.supplier as clay_supplier, c.cone_min as clay_cone_min, c.cone_max as clay_cone_max,
+c.fired_colour as clay_fired_colour, c.grog as clay_grog, c.is_active as clay_is_active
 FROM works w
 JOIN (
-    SELECT work_id, current_state as current_state_id, created_at as current_state_transitioned
-    FROM events
-    WHERE id IN (
-        SELECT MAX(id)
+    SELECT ev.work_id, s.key as current_state, ev.occurred_at as current_state_transitioned
+    FROM (
+        SELECT work_id, current_state, occurred_at,
+        ROW_NUMBER() OVER (PARTITION BY work_id ORDER BY occurred_at DESC, id DESC) AS position
         FROM events
-        GROUP BY work_id
-    )
+    ) ev
+    JOIN states s ON ev.current_state = s.id
+    WHERE ev.position = 1
 ) e ON w.id = e.work_id
-JOIN clays c ON w.clay_id = c.id";
+JOIN clays c ON w.clay_id = c.id
+JOIN workflows wf ON w.workflow_id = wf.id";
 
 #[derive(sqlx::FromRow, Serialize)]
 pub(crate) struct WorkDTO {
@@ -37,16 +73,36 @@ pub(crate) struct WorkDTO {
     clay_name: String,
     clay_description: Option<String>,
     clay_shrinkage: f64,
-    current_state_id: i32,
+    clay_coe: Option<f64>,
+    clay_supplier: Option<String>,
+    clay_cone_min: Option<String>,
+    clay_cone_max: Option<String>,
+    clay_fired_colour: Option<String>,
+    clay_grog: Option<f64>,
+    clay_is_active: bool,
+    current_state: String,
     current_state_transitioned: NaiveDateTime,
     glaze_description: Option<String>,
+    glaze_recipe: Option<String>,
+    glaze_umf: Option<String>,
     header_key: Option<String>,
     thumbnail_key: Option<String>,
     created_at: NaiveDateTime,
     is_multiple: bool,
+    footprint_width_cm: Option<f64>,
+    footprint_depth_cm: Option<f64>,
+    height_cm: Option<f64>,
+    workflow: String,
+    firing_count: i32,
+    /// JSON array of tag names.
+    tags: String,
 }
 
-pub(crate) fn workdto_to_work(workdto: WorkDTO, _appstate: &AppState) -> Work {
+pub(crate) fn workdto_to_work(
+    workdto: WorkDTO,
+    mut glazes: Vec<GlazeLayer>,
+    _appstate: &AppState,
+) -> Work {
     let images = Images {
         header: workdto.header_key,
         thumbnail: workdto.thumbnail_key,
@@ -57,8 +113,32 @@ pub(crate) fn workdto_to_work(workdto: WorkDTO, _appstate: &AppState) -> Work {
         name: workdto.clay_name,
         description: workdto.clay_description,
         shrinkage: workdto.clay_shrinkage,
+        coe: workdto.clay_coe,
+        supplier: workdto.clay_supplier,
+        cone_min: workdto.clay_cone_min,
+        cone_max: workdto.clay_cone_max,
+        fired_colour: workdto.clay_fired_colour,
+        grog: workdto.clay_grog,
+        is_active: workdto.clay_is_active,
     };
 
+    for layer in glazes.iter_mut() {
+        layer.fit = clay
+            .coe
+            .zip(layer.glaze.coe)
+            .map(|(clay_coe, glaze_coe)| glaze_fit(clay_coe, glaze_coe));
+    }
+
+    let glaze_chemistry = workdto
+        .glaze_recipe
+        .zip(workdto.glaze_umf)
+        .and_then(|(recipe, umf)| {
+            Some(GlazeChemistry {
+                recipe: serde_json::from_str(&recipe).ok()?,
+                umf: serde_json::from_str(&umf).ok()?,
+            })
+        });
+
     Work {
         id: workdto.id,
         project: (ApiResource::Project, workdto.project_id).into(),
@@ -66,74 +146,134 @@ pub(crate) fn workdto_to_work(workdto: WorkDTO, _appstate: &AppState) -> Work {
         notes: workdto.notes,
         clay,
         current_state: CurrentState {
-            state: workdto.current_state_id.into(),
+            state: workdto.current_state.into(),
             transitioned_at: workdto.current_state_transitioned,
         },
+        glazes,
         glaze_description: workdto.glaze_description,
+        glaze_chemistry,
         images,
         created_at: workdto.created_at,
         is_multiple: workdto.is_multiple,
+        footprint_width_cm: workdto.footprint_width_cm,
+        footprint_depth_cm: workdto.footprint_depth_cm,
+        height_cm: workdto.height_cm,
+        workflow: workdto.workflow,
+        firing_count: workdto.firing_count,
+        tags: serde_json::from_str(&workdto.tags).unwrap_or_default(),
     }
 }
 
-pub(crate) async fn works(State(appstate): State<AppState>) -> JsonResult<Vec<Work>> {
-    sqlx::query_as::<_, WorkDTO>(WORK_DTO_QUERY)
-        .fetch_all(&appstate.pool)
-        .await
-        .map(|works| {
-            works
-                .into_iter()
-                .map(|w| workdto_to_work(w, &appstate))
-                .collect::<Vec<Work>>()
+/// Runs a `WORK_DTO_QUERY` based query and attaches the glaze layers of each
+/// resulting work.
+pub(crate) async fn fetch_works<'q>(
+    appstate: &AppState,
+    query: QueryAs<'q, Sqlite, WorkDTO, SqliteArguments<'q>>,
+) -> Result<Vec<Work>, sqlx::Error> {
+    let workdtos = query.fetch_all(&appstate.pool).await?;
+    let work_ids = workdtos.iter().map(|w| w.id).collect::<Vec<i32>>();
+    let mut layers = glaze_layers(appstate, &work_ids).await?;
+
+    Ok(workdtos
+        .into_iter()
+        .map(|w| {
+            let glazes = layers.remove(&w.id).unwrap_or_default();
+            workdto_to_work(w, glazes, appstate)
         })
-        .into()
+        .collect::<Vec<Work>>())
+}
+
+pub(crate) async fn works(
+    State(appstate): State<AppState>,
+    Query(filter): Query<TagFilter>,
+) -> JsonResult<Vec<Work>> {
+    let (condition, tags) = filter
+        .condition("w.id", "work_tags", "work_id")
+        .map(|(condition, tags)| (format!("WHERE {}", condition), tags))
+        .unwrap_or_default();
+
+    let query_string = format!("{} {}", WORK_DTO_QUERY, condition);
+    let mut query = sqlx::query_as::<_, WorkDTO>(&query_string);
+    for tag in tags {
+        query = query.bind(tag);
+    }
+    fetch_works(&appstate, query).await.into()
 }
 
 pub(crate) async fn work(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
 ) -> OptionalResult<Work> {
-    sqlx::query_as::<_, WorkDTO>(&format!("{} {}", WORK_DTO_QUERY, "WHERE w.id = ?"))
-        .bind(id)
-        .fetch_optional(&appstate.pool)
+    let query = format!("{} {}", WORK_DTO_QUERY, "WHERE w.id = ?");
+    fetch_works(&appstate, sqlx::query_as::<_, WorkDTO>(&query).bind(id))
         .await
-        .map(|work_dto| work_dto.map(|w| workdto_to_work(w, &appstate)))
+        .map(|works| works.into_iter().next())
         .into()
 }
 
-#[derive(sqlx::FromRow, Debug)]
-struct EventDTO {
-    id: i32,
-    work_id: i32,
-    previous_state_id: Option<i32>,
-    current_state_id: i32,
-    created_at: NaiveDateTime,
+/// Finds works left in their current state for longer than the threshold of
+/// that state, or of the state for works of their clay, most overdue first.
+pub(crate) async fn find_stale_works(appstate: &AppState) -> Result<Vec<StaleWork>, sqlx::Error> {
+    let works = fetch_works(appstate, sqlx::query_as::<_, WorkDTO>(WORK_DTO_QUERY)).await?;
+
+    let clay_thresholds = sqlx::query_as::<_, (i32, String, f64)>(
+        "SELECT ct.clay_id, s.key, ct.max_days
+        FROM clay_state_thresholds ct
+        JOIN states s ON ct.state_id = s.id",
+    )
+    .fetch_all(&appstate.pool)
+    .await?
+    .into_iter()
+    .map(|(clay_id, state, max_days)| ((clay_id, state), max_days))
+    .collect::<HashMap<(i32, String), f64>>();
+
+    let machine = appstate.states.read().await.clone();
+    let now = Utc::now().naive_utc();
+
+    let mut stale = works
+        .into_iter()
+        .filter_map(|work| {
+            let state = &work.current_state.state;
+            let max_days = clay_thresholds
+                .get(&(work.clay.id, state.key().to_string()))
+                .copied()
+                .or_else(|| {
+                    machine
+                        .states()
+                        .iter()
+                        .find(|s| &s.key == state)
+                        .and_then(|s| s.max_days)
+                })?;
+            let (days_in_state, days_overdue) =
+                staleness(work.current_state.transitioned_at, now, max_days)?;
+
+            Some(StaleWork {
+                work: (ApiResource::Work, work.id).into(),
+                name: work.name,
+                clay: (ApiResource::Clay, work.clay.id).into(),
+                current_state: work.current_state,
+                days_in_state,
+                max_days,
+                days_overdue,
+            })
+        })
+        .collect::<Vec<StaleWork>>();
+    stale.sort_by(|a, b| b.days_overdue.total_cmp(&a.days_overdue));
+    Ok(stale)
 }
 
-impl From<EventDTO> for Event {
-    fn from(event: EventDTO) -> Self {
-        Event {
-            id: event.id,
-            work: (ApiResource::Work, event.work_id).into(),
-            previous_state: event.previous_state_id.map(WorkState::from),
-            current_state: event.current_state_id.into(),
-            created_at: event.created_at,
-        }
-    }
+pub(crate) async fn stale_works(State(appstate): State<AppState>) -> JsonResult<Vec<StaleWork>> {
+    find_stale_works(&appstate).await.into()
 }
 
 pub(crate) async fn events(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
 ) -> JsonResult<Vec<Event>> {
-    sqlx::query_as::<_, EventDTO>(
-        "SELECT e.id, e.work_id, s1.id AS previous_state_id,
-        s2.id AS current_state_id, e.created_at
-        FROM events e
-        LEFT JOIN states s1 ON e.previous_state = s1.id
-        LEFT JOIN states s2 ON e.current_state = s2.id
-        WHERE e.work_id = ?",
-    )
+    sqlx::query_as::<_, EventDTO>(&format!(
+        "{} {}",
+        EVENT_DTO_QUERY, "WHERE e.work_id = ? ORDER BY e.occurred_at, e.id"
+    ))
     .bind(id)
     .fetch_all(&appstate.pool)
     .await
@@ -143,77 +283,327 @@ pub(crate) async fn events(
 
 // PUT
 
-pub(crate) async fn put_work(
-    Path(id): Path<i32>,
-    State(appstate): State<AppState>,
-    ExtractJson(data): ExtractJson<PutWork>,
-) -> EmptyResult {
-    sqlx::query(
+async fn insert_glaze_layers(
+    tx: &mut Transaction<'_, Sqlite>,
+    work_id: i32,
+    layers: &[PutGlazeLayer],
+) -> Result<(), Error> {
+    if layers.iter().any(|layer| layer.coats <= 0) {
+        return Err(Error::InvalidCoats);
+    }
+
+    for (index, layer) in layers.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO work_glazes (work_id, glaze_id, layer, method, coats, area)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(work_id)
+        .bind(layer.glaze_id)
+        .bind(index as i32 + 1)
+        .bind(&layer.method)
+        .bind(layer.coats)
+        .bind(&layer.area)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Resolves the key of a workflow to its id, falling back to the default
+/// workflow if none is given.
+async fn workflow_id(appstate: &AppState, key: Option<&str>) -> Result<i32, Error> {
+    let key = key.unwrap_or(StateMachine::DEFAULT_WORKFLOW);
+    appstate
+        .states
+        .read()
+        .await
+        .workflow(key)
+        .map(|w| w.id)
+        .ok_or_else(|| Error::UnknownWorkflow(key.to_string()))
+}
+
+async fn update_work(appstate: &AppState, id: i32, data: &PutWork) -> Result<(), Error> {
+    let workflow_id = match &data.workflow {
+        Some(key) => Some(workflow_id(appstate, Some(key)).await?),
+        None => None,
+    };
+    let mut tx = appstate.pool.begin().await?;
+
+    let updated = sqlx::query(
         "UPDATE works
         SET project_id=?, name=?, notes=?, clay_id=?, glaze_description=?,
-        header_key=?, thumbnail_key=?, is_multiple=?
+        header_key=?, thumbnail_key=?, is_multiple=?, footprint_width_cm=?, footprint_depth_cm=?,
+        height_cm=?, workflow_id=COALESCE(?, workflow_id)
         WHERE id=?",
     )
     .bind(data.project_id)
-    .bind(data.name)
-    .bind(data.notes)
+    .bind(&data.name)
+    .bind(&data.notes)
     .bind(data.clay_id)
-    .bind(data.glaze_description)
-    .bind(data.header)
-    .bind(data.thumbnail)
+    .bind(&data.glaze_description)
+    .bind(&data.header)
+    .bind(&data.thumbnail)
     .bind(data.is_multiple)
+    .bind(data.footprint_width_cm)
+    .bind(data.footprint_depth_cm)
+    .bind(data.height_cm)
+    .bind(workflow_id)
     .bind(id)
-    .execute(&appstate.pool)
-    .await
-    .into()
+    .execute(&mut tx)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    if let Some(glazes) = &data.glazes {
+        sqlx::query("DELETE FROM work_glazes WHERE work_id = ?")
+            .bind(id)
+            .execute(&mut tx)
+            .await?;
+
+        insert_glaze_layers(&mut tx, id, glazes).await?;
+    }
+
+    if let Some(tags) = &data.tags {
+        replace_work_tags(&mut tx, id, tags).await?;
+    }
+    reindex(&mut tx, SearchResource::Work, id).await?;
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::WorkUpdated,
+        (ApiResource::Work, id).into(),
+        json!({ "name": data.name }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
 }
 
-pub(crate) async fn put_state(
+pub(crate) async fn put_work(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
-    ExtractJson(data): ExtractJson<WorkState>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
-    let latest_event = sqlx::query_as::<_, EventDTO>(
-        "SELECT id, work_id, previous_state as previous_state_id, current_state as current_state_id, created_at
-        FROM events
-        WHERE work_id = ?
-        ORDER BY created_at DESC
-        LIMIT 1"
-    )
-        .bind(id)
-        .fetch_one(&appstate.pool)
-        .await
-        .map_err(internal_error)?;
+    ExtractJson(data): ExtractJson<PutWork>,
+) -> EmptyResult {
+    EmptyResult(update_work(&appstate, id, &data).await)
+}
 
-    let current_state = WorkState::from(latest_event.current_state_id);
-    if is_valid_transition(current_state.clone(), data.clone()) {
-        let new_previous_state_id: &i32 = &current_state.into();
-        let new_current_state_id: &i32 = &data.into();
+async fn update_chemistry(appstate: &AppState, id: i32, recipe: &Recipe) -> Result<(), Error> {
+    let umf = recipe_umf(appstate, recipe).await?;
 
-        sqlx::query(
-            "INSERT INTO events (work_id, previous_state, current_state)
-            VALUES (?, ?, ?)",
-        )
+    let updated = sqlx::query("UPDATE works SET glaze_recipe=?, glaze_umf=? WHERE id=?")
+        .bind(serde_json::to_string(recipe).map_err(internal_error)?)
+        .bind(serde_json::to_string(&umf).map_err(internal_error)?)
         .bind(id)
-        .bind(new_previous_state_id)
-        .bind(new_current_state_id)
         .execute(&appstate.pool)
-        .await
-        .map(|_| ())
-        .map_err(internal_error)
-    } else {
-        Err(Error::InvalidStateTransition)
+        .await?
+        .rows_affected();
+    if updated == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn put_chemistry(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<Recipe>,
+) -> EmptyResult {
+    EmptyResult(update_chemistry(&appstate, id, &data).await)
+}
+
+/// Moves a work to a new state, provided its workflow allows the transition
+/// from its current state, optionally recording the firing it went
+/// through.
+pub(crate) async fn transition_work(
+    tx: &mut Transaction<'_, Sqlite>,
+    machine: &StateMachine,
+    id: i32,
+    transition: &Transition,
+    firing_id: Option<i32>,
+) -> Result<(), Error> {
+    let latest_event = sqlx::query_as::<_, EventDTO>(&format!(
+        "{} {}",
+        EVENT_DTO_QUERY, "WHERE e.work_id = ? ORDER BY e.occurred_at DESC, e.id DESC LIMIT 1"
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(Error::ResourceNotFound)?;
+
+    let workflow_id = sqlx::query_scalar::<_, i32>("SELECT workflow_id FROM works WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let current_state = WorkState::from(latest_event.current_state);
+    if !machine.is_valid_transition(workflow_id, &current_state, &transition.state) {
+        return Err(Error::InvalidStateTransition);
+    }
+
+    let is_firing = current_state == WorkState::AWAITING_BISQUE_FIRING
+        || current_state == WorkState::AWAITING_GLAZE_FIRING;
+    let has_firing_details = transition.schedule_id.is_some() || transition.kiln_id.is_some();
+    if has_firing_details && !is_firing {
+        return Err(Error::NotLeavingFiring);
+    }
+
+    if let Some(schedule_id) = transition.schedule_id {
+        sqlx::query_scalar::<_, i32>("SELECT id FROM firing_schedules WHERE id = ?")
+            .bind(schedule_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(Error::ResourceNotFound)?;
     }
+
+    if let Some(kiln_id) = transition.kiln_id {
+        sqlx::query_scalar::<_, i32>("SELECT id FROM kilns WHERE id = ?")
+            .bind(kiln_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(Error::ResourceNotFound)?;
+    }
+
+    if let Some(occurred_at) = transition.occurred_at {
+        if occurred_at < latest_event.occurred_at || occurred_at > Utc::now().naive_utc() {
+            return Err(Error::InvalidOccurredAt);
+        }
+    }
+
+    if !transition.is_valid_defect() {
+        return Err(Error::NotFailing);
+    }
+
+    let new_previous_state_id = machine.id(&current_state);
+    let new_current_state_id = machine.id(&transition.state);
+    let measurements = transition.measurements.clone().unwrap_or_default();
+
+    let event_id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO events (work_id, previous_state, current_state, height, width, weight, wall_thickness, firing_id, schedule_id,
+        kiln_id, defect, defect_note, note, occurred_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, strftime('%Y-%m-%dT%H:%M:%f', 'now')))
+        RETURNING id",
+    )
+    .bind(id)
+    .bind(new_previous_state_id)
+    .bind(new_current_state_id)
+    .bind(measurements.height)
+    .bind(measurements.width)
+    .bind(measurements.weight)
+    .bind(measurements.wall_thickness)
+    .bind(firing_id)
+    .bind(transition.schedule_id)
+    .bind(transition.kiln_id)
+    .bind(transition.defect)
+    .bind(&transition.defect_note)
+    .bind(&transition.note)
+    .bind(transition.occurred_at.map(|t| t.format(TIMESTAMP_FORMAT).to_string()))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (index, image_key) in transition.images.iter().enumerate() {
+        sqlx::query("INSERT INTO event_images (event_id, position, image_key) VALUES (?, ?, ?)")
+            .bind(event_id)
+            .bind(index as i32 + 1)
+            .bind(image_key)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    enqueue(
+        &mut *tx,
+        WebhookEvent::WorkTransitioned,
+        (ApiResource::Work, id).into(),
+        json!({ "previous_state": current_state, "state": transition.state }),
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn update_state(appstate: &AppState, id: i32, transition: &Transition) -> Result<(), Error> {
+    let machine = appstate.states.read().await.clone();
+    let mut tx = appstate.pool.begin().await?;
+    transition_work(&mut tx, &machine, id, transition, None).await?;
+    tx.commit().await?;
+    notify_events(appstate);
+    Ok(())
+}
+
+pub(crate) async fn put_state(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+    ExtractJson(data): ExtractJson<PutState>,
+) -> EmptyResult {
+    EmptyResult(update_state(&appstate, id, &Transition::from(data)).await)
+}
+
+pub(crate) async fn shrinkage(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> OptionalResult<MeasuredShrinkage> {
+    sqlx::query_as::<_, EventDTO>(&format!(
+        "{} {}",
+        EVENT_DTO_QUERY, "WHERE e.work_id = ? ORDER BY e.occurred_at, e.id"
+    ))
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await
+    .map(|events| {
+        let events = events.into_iter().map(Event::from).collect::<Vec<Event>>();
+        work_shrinkage(&events)
+    })
+    .into()
+}
+
+#[derive(sqlx::FromRow)]
+struct RevertedEventDTO {
+    event: String,
+    reverted_at: NaiveDateTime,
+}
+
+pub(crate) async fn reverted_events(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> JsonResult<Vec<RevertedEvent>> {
+    sqlx::query_as::<_, RevertedEventDTO>(
+        "SELECT event, reverted_at FROM event_reverts WHERE work_id = ? ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(&appstate.pool)
+    .await
+    .map(|reverts| {
+        reverts
+            .into_iter()
+            .map(|revert| RevertedEvent {
+                event: serde_json::from_str(&revert.event).unwrap_or_default(),
+                reverted_at: revert.reverted_at,
+            })
+            .collect::<Vec<RevertedEvent>>()
+    })
+    .into()
 }
 
 // POST
 
-async fn insert_work(appstate: &AppState, post_work: &PostWork) -> Result<i32, sqlx::Error> {
-    let initial_state_id: &i32 = &post_work.state.clone().into();
+async fn insert_work(appstate: &AppState, post_work: &PostWork) -> Result<i32, Error> {
+    let initial_state_id = appstate
+        .states
+        .read()
+        .await
+        .id(&post_work.state)
+        .ok_or_else(|| Error::UnknownState(post_work.state.key().to_string()))?;
+    let workflow_id = workflow_id(appstate, post_work.workflow.as_deref()).await?;
+    let mut tx = appstate.pool.begin().await?;
 
     let id = sqlx::query_scalar::<_, i32>(
-        "INSERT INTO works (project_id, name, notes, clay_id, glaze_description, header_key, thumbnail_key, is_multiple)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "INSERT INTO works (project_id, name, notes, clay_id, glaze_description, header_key, thumbnail_key, is_multiple,
+        footprint_width_cm, footprint_depth_cm, height_cm, workflow_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         RETURNING id"
     )
     .bind(post_work.project_id)
@@ -223,19 +613,43 @@ async fn insert_work(appstate: &AppState, post_work: &PostWork) -> Result<i32, s
     .bind(&post_work.glaze_description)
     .bind(&post_work.header)
     .bind(&post_work.thumbnail)
-    .bind(&post_work.is_multiple)
-    .fetch_one(&appstate.pool)
+    .bind(post_work.is_multiple)
+    .bind(post_work.footprint_width_cm)
+    .bind(post_work.footprint_depth_cm)
+    .bind(post_work.height_cm)
+    .bind(workflow_id)
+    .fetch_one(&mut tx)
     .await?;
 
+    insert_glaze_layers(&mut tx, id, &post_work.glazes).await?;
+    replace_work_tags(&mut tx, id, &post_work.tags).await?;
+    reindex(&mut tx, SearchResource::Work, id).await?;
+
+    let measurements = post_work.measurements.clone().unwrap_or_default();
     sqlx::query(
-        "INSERT INTO events (work_id, current_state)
-        VALUES (?, ?)",
+        "INSERT INTO events (work_id, current_state, height, width, weight, wall_thickness)
+        VALUES (?, ?, ?, ?, ?, ?)",
     )
     .bind(id)
     .bind(initial_state_id)
-    .execute(&appstate.pool)
+    .bind(measurements.height)
+    .bind(measurements.width)
+    .bind(measurements.weight)
+    .bind(measurements.wall_thickness)
+    .execute(&mut tx)
+    .await?;
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::WorkCreated,
+        (ApiResource::Work, id).into(),
+        json!({ "name": post_work.name, "state": post_work.state }),
+    )
     .await?;
 
+    tx.commit().await?;
+    notify_events(appstate);
+
     Ok(id)
 }
 
@@ -243,22 +657,113 @@ pub(crate) async fn post_work(
     State(appstate): State<AppState>,
     ExtractJson(data): ExtractJson<PostWork>,
 ) -> JsonResult<i32> {
-    insert_work(&appstate, &data).await.into()
+    JsonResult(insert_work(&appstate, &data).await)
 }
 
 // DELETE
 
-async fn delete_work_and_events(appstate: &AppState, id: &i32) -> Result<(), sqlx::Error> {
-    sqlx::query("DELETE FROM events WHERE work_id = ?")
+/// Undoes the latest transition of a work, moving the event to the reverted
+/// events of the work so a record of it is kept.
+async fn remove_latest_event(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    let latest_event = sqlx::query_as::<_, EventDTO>(&format!(
+        "{} {}",
+        EVENT_DTO_QUERY, "WHERE e.work_id = ? ORDER BY e.occurred_at DESC, e.id DESC LIMIT 1"
+    ))
+    .bind(id)
+    .fetch_optional(&mut tx)
+    .await?
+    .ok_or(Error::ResourceNotFound)?;
+
+    if latest_event.previous_state.is_none() {
+        return Err(Error::InitialEvent);
+    }
+    // Undoing these would leave the firing completed with the work still
+    // loaded into it.
+    if latest_event.firing_id.is_some() {
+        return Err(Error::FiringEvent);
+    }
+
+    let event_id = latest_event.id;
+    let transition = json!({
+        "previous_state": latest_event.current_state,
+        "state": latest_event.previous_state,
+        "reverted": true,
+    });
+    let event = serde_json::to_string(&Event::from(latest_event)).map_err(internal_error)?;
+    sqlx::query("INSERT INTO event_reverts (work_id, event) VALUES (?, ?)")
         .bind(id)
-        .execute(&appstate.pool)
+        .bind(event)
+        .execute(&mut tx)
         .await?;
 
-    sqlx::query("DELETE FROM works WHERE id = ?")
+    for query in [
+        "DELETE FROM event_images WHERE event_id = ?",
+        "DELETE FROM firing_log_events WHERE event_id = ?",
+        "DELETE FROM events WHERE id = ?",
+    ] {
+        sqlx::query(query).bind(event_id).execute(&mut tx).await?;
+    }
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::WorkTransitioned,
+        (ApiResource::Work, id).into(),
+        transition,
+    )
+    .await?;
+
+    tx.commit().await?;
+    notify_events(appstate);
+    Ok(())
+}
+
+pub(crate) async fn revert_state(
+    Path(id): Path<i32>,
+    State(appstate): State<AppState>,
+) -> EmptyResult {
+    EmptyResult(remove_latest_event(&appstate, id).await)
+}
+
+async fn delete_work_and_events(appstate: &AppState, id: i32) -> Result<(), Error> {
+    let mut tx = appstate.pool.begin().await?;
+
+    for query in [
+        "DELETE FROM event_reverts WHERE work_id = ?",
+        "DELETE FROM work_glazes WHERE work_id = ?",
+        "DELETE FROM work_tags WHERE work_id = ?",
+        "DELETE FROM firing_works WHERE work_id = ?",
+        "DELETE FROM firing_log_events
+        WHERE event_id IN (SELECT id FROM events WHERE work_id = ?)",
+        "DELETE FROM event_images
+        WHERE event_id IN (SELECT id FROM events WHERE work_id = ?)",
+        "DELETE FROM events WHERE work_id = ?",
+    ] {
+        sqlx::query(query).bind(id).execute(&mut tx).await?;
+    }
+
+    let deleted = sqlx::query("DELETE FROM works WHERE id = ?")
         .bind(id)
-        .execute(&appstate.pool)
-        .await?;
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(Error::ResourceNotFound);
+    }
+
+    remove_unused_tags(&mut tx).await?;
+    unindex(&mut tx, SearchResource::Work, id).await?;
+
+    enqueue(
+        &mut tx,
+        WebhookEvent::WorkDeleted,
+        (ApiResource::Work, id).into(),
+        json!({}),
+    )
+    .await?;
 
+    tx.commit().await?;
     Ok(())
 }
 
@@ -266,5 +771,5 @@ pub(crate) async fn delete_work(
     Path(id): Path<i32>,
     State(appstate): State<AppState>,
 ) -> EmptyResult {
-    delete_work_and_events(&appstate, &id).await.into()
+    EmptyResult(delete_work_and_events(&appstate, id).await)
 }
@@ -0,0 +1,265 @@
+use crate::config::KilnConfig;
+use crate::models::{ApiResource, PlacedWork, ShelfLoad};
+
+/// A work to be loaded, with its dimensions in centimetres.
+pub(crate) struct LoadItem {
+    pub(crate) id: i32,
+    pub(crate) width: f64,
+    pub(crate) depth: f64,
+    pub(crate) height: f64,
+}
+
+/// A strip across the width of a shelf, as deep as the first work placed in
+/// it.
+struct Row {
+    y: f64,
+    depth: f64,
+    used_width: f64,
+}
+
+struct Shelf {
+    post_height: f64,
+    rows: Vec<Row>,
+    works: Vec<PlacedWork>,
+}
+
+impl Shelf {
+    fn new(post_height: f64) -> Self {
+        Shelf {
+            post_height,
+            rows: Vec::new(),
+            works: Vec::new(),
+        }
+    }
+
+    /// Places an item in the first row it fits, turning it if need be, or
+    /// starts a new row behind the others.
+    fn place(&mut self, kiln: &KilnConfig, item: &LoadItem) -> bool {
+        let orientations = [
+            (item.width, item.depth, false),
+            (item.depth, item.width, true),
+        ];
+
+        let mut placement = None;
+        for row in self.rows.iter_mut() {
+            placement = orientations
+                .iter()
+                .find(|(w, d, _)| row.used_width + w <= kiln.shelf_width && *d <= row.depth)
+                .map(|(w, d, rotated)| {
+                    let x = row.used_width;
+                    row.used_width += w + kiln.spacing;
+                    (x, row.y, *w, *d, *rotated)
+                });
+            if placement.is_some() {
+                break;
+            }
+        }
+
+        if placement.is_none() {
+            let y = self
+                .rows
+                .last()
+                .map_or(0.0, |r| r.y + r.depth + kiln.spacing);
+            // The shallower orientation leaves the most room for rows behind.
+            let mut fitting = orientations
+                .iter()
+                .filter(|(w, d, _)| *w <= kiln.shelf_width && y + d <= kiln.shelf_depth)
+                .collect::<Vec<_>>();
+            fitting.sort_by(|a, b| a.1.total_cmp(&b.1));
+            placement = fitting.first().map(|(w, d, rotated)| {
+                self.rows.push(Row {
+                    y,
+                    depth: *d,
+                    used_width: w + kiln.spacing,
+                });
+                (0.0, y, *w, *d, *rotated)
+            });
+        }
+
+        match placement {
+            Some((x, y, width, depth, rotated)) => {
+                self.works.push(PlacedWork {
+                    work: (ApiResource::Work, item.id).into(),
+                    x,
+                    y,
+                    width,
+                    depth,
+                    height: item.height,
+                    rotated,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Shortest post that leaves enough clearance above an item.
+fn post_for(kiln: &KilnConfig, height: f64) -> Option<f64> {
+    kiln.post_heights
+        .iter()
+        .copied()
+        .filter(|post| *post >= height + kiln.spacing)
+        .min_by(f64::total_cmp)
+}
+
+fn stack_height(kiln: &KilnConfig, shelves: &[Shelf]) -> f64 {
+    shelves
+        .iter()
+        .map(|s| kiln.shelf_thickness + s.post_height)
+        .sum()
+}
+
+/// Plans a kiln load from items in order of priority. Each item goes on the
+/// first shelf with room for it, raising that shelf's posts if the kiln is
+/// tall enough, before a new shelf is added. Returns the shelves from the
+/// bottom up, and the ids of the items left over.
+pub(crate) fn plan_load(kiln: &KilnConfig, items: &[LoadItem]) -> (Vec<ShelfLoad>, Vec<i32>) {
+    let mut shelves: Vec<Shelf> = Vec::new();
+    let mut leftover: Vec<i32> = Vec::new();
+
+    for item in items {
+        let post = match post_for(kiln, item.height) {
+            Some(post) => post,
+            None => {
+                leftover.push(item.id);
+                continue;
+            }
+        };
+
+        if shelves
+            .iter_mut()
+            .filter(|s| s.post_height >= post)
+            .any(|s| s.place(kiln, item))
+        {
+            continue;
+        }
+
+        let height = stack_height(kiln, &shelves);
+        if shelves
+            .iter_mut()
+            .filter(|s| s.post_height < post)
+            .filter(|s| height - s.post_height + post <= kiln.interior_height)
+            .any(|s| {
+                let placed = s.place(kiln, item);
+                if placed {
+                    s.post_height = post;
+                }
+                placed
+            })
+        {
+            continue;
+        }
+
+        let mut shelf = Shelf::new(post);
+        if height + kiln.shelf_thickness + post <= kiln.interior_height && shelf.place(kiln, item) {
+            shelves.push(shelf);
+        } else {
+            leftover.push(item.id);
+        }
+    }
+
+    let shelf_area = kiln.shelf_width * kiln.shelf_depth;
+    let mut elevation = 0.0;
+    let loads = shelves
+        .into_iter()
+        .enumerate()
+        .map(|(index, shelf)| {
+            elevation += kiln.shelf_thickness;
+            let load = ShelfLoad {
+                position: index + 1,
+                elevation,
+                post_height: shelf.post_height,
+                utilisation: shelf.works.iter().map(|w| w.width * w.depth).sum::<f64>()
+                    / shelf_area,
+                works: shelf.works,
+            };
+            elevation += shelf.post_height;
+            load
+        })
+        .collect::<Vec<ShelfLoad>>();
+
+    (loads, leftover)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kiln() -> KilnConfig {
+        KilnConfig {
+            shelf_width: 40.0,
+            shelf_depth: 40.0,
+            shelf_thickness: 2.0,
+            interior_height: 50.0,
+            post_heights: vec![10.0, 20.0, 30.0],
+            spacing: 0.0,
+        }
+    }
+
+    fn item(id: i32, width: f64, depth: f64, height: f64) -> LoadItem {
+        LoadItem {
+            id,
+            width,
+            depth,
+            height,
+        }
+    }
+
+    fn ids(shelf: &ShelfLoad) -> Vec<i32> {
+        shelf.works.iter().map(|w| w.work.id).collect()
+    }
+
+    #[test]
+    fn test_plan_load() {
+        let kiln = kiln();
+        let items = [
+            item(1, 20.0, 20.0, 8.0),
+            item(2, 20.0, 20.0, 8.0),
+            item(3, 20.0, 20.0, 15.0),
+            item(4, 40.0, 20.0, 8.0),
+            item(5, 20.0, 20.0, 25.0),
+            item(6, 10.0, 10.0, 40.0),
+        ];
+
+        let (shelves, leftover) = plan_load(&kiln, &items);
+
+        // The first shelf's posts are raised for the third work, and again for
+        // the fifth, which takes the last space left after the fourth, too
+        // wide for it, starts a new shelf.
+        assert_eq!(shelves.len(), 2);
+        assert_eq!(ids(&shelves[0]), vec![1, 2, 3, 5]);
+        assert_eq!(shelves[0].post_height, 30.0);
+        assert_eq!(shelves[0].elevation, 2.0);
+        assert_eq!(shelves[0].utilisation, 1.0);
+
+        assert_eq!(ids(&shelves[1]), vec![4]);
+        assert_eq!(shelves[1].post_height, 10.0);
+        assert_eq!(shelves[1].elevation, 34.0);
+        assert_eq!(shelves[1].utilisation, 0.5);
+
+        // Too tall for any post.
+        assert_eq!(leftover, vec![6]);
+    }
+
+    #[test]
+    fn test_plan_load_rotation_and_overflow() {
+        let kiln = kiln();
+        let items = [
+            item(1, 30.0, 40.0, 5.0),
+            item(2, 40.0, 10.0, 5.0),
+            item(3, 40.0, 40.0, 25.0),
+            item(4, 40.0, 40.0, 5.0),
+        ];
+
+        let (shelves, leftover) = plan_load(&kiln, &items);
+
+        // The first work is turned so the second fits behind it.
+        assert!(shelves[0].works[0].rotated);
+        assert_eq!(shelves[0].works[1].y, 30.0);
+
+        // A third shelf wouldn't fit under the lid.
+        assert_eq!(shelves.len(), 2);
+        assert_eq!(leftover, vec![4]);
+    }
+}
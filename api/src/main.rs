@@ -1,41 +1,85 @@
+mod chemistry;
 mod config;
 mod error;
+mod firing_log;
 mod handlers;
 mod jwt;
+mod kiln_load;
 mod models;
+mod notifications;
 mod result;
+mod webhooks;
 
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_s3::{config::Region, Client};
 use axum::{
     middleware,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
 use std::net::SocketAddr;
 use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
 use tower_http::trace::{self, TraceLayer};
 use tracing::Level;
 
 use config::Config;
 use handlers::auth::{auth as is_authed, login};
-use handlers::clay::clays;
-use handlers::event::events;
+use handlers::clay::{
+    clay, clays, compatible_glazes, delete_clay, measured_shrinkage, post_clay, put_clay,
+    put_stale_thresholds, shrinkage, stale_thresholds,
+};
+use handlers::event::{events, stream as event_stream};
+use handlers::firing::{
+    delete_firing, firing, firings, post_complete, post_firing, put_firing, put_works,
+};
+use handlers::firing_log::{delete_firing_log, firing_log, post_firing_log};
+use handlers::glaze::{
+    analysis, batch, delete_glaze, glaze, glaze_umf, glazes, post_glaze, put_analysis, put_glaze,
+    put_recipe, recipe,
+};
 use handlers::image::upload_image_to_s3;
+use handlers::kiln::{delete_kiln, kiln, kilns, load_plan, post_kiln, put_kiln};
+use handlers::maintenance::{
+    counters, delete_maintenance, maintenance, maintenance_due, post_maintenance,
+};
+use handlers::material::{delete_material, material, materials, post_material, put_material, umf};
+use handlers::notification::notifications;
 use handlers::project::{
     delete_project, post_project, project, projects, put_project, works as project_works,
 };
+use handlers::schedule::{
+    controller_program, delete_schedule, post_schedule, program_listing, put_schedule, schedule,
+    schedules,
+};
+use handlers::search::search;
+use handlers::state::{
+    delete_state, delete_workflow, load_state_machine, post_state, post_workflow,
+    put_state as put_state_definition, put_workflow, states, workflow, workflows,
+};
+use handlers::stats::defects;
+use handlers::tag::tags;
+use handlers::webhook::{
+    delete_webhook, deliveries, post_webhook, put_webhook, retry_delivery, webhook, webhooks,
+};
 use handlers::work::{
-    delete_work, events as work_events, post_work, put_state, put_work, work, works,
+    delete_work, events as work_events, post_work, put_chemistry, put_state, put_work,
+    revert_state, reverted_events, shrinkage as work_shrinkage, stale_works, work, works,
 };
 use jwt::auth;
+use models::StateMachine;
 
 #[derive(Clone)]
 pub struct AppState {
     config: Config,
     pool: SqlitePool,
     s3_client: Client,
+    states: Arc<RwLock<StateMachine>>,
+    /// Signalled whenever events are recorded or undone, to wake up event
+    /// streams.
+    new_events: Arc<watch::Sender<()>>,
 }
 
 #[tokio::main]
@@ -72,6 +116,10 @@ async fn main() {
         .await
         .expect("cannot connect to db");
 
+    let state_machine = load_state_machine(&pool)
+        .await
+        .expect("cannot load state machine");
+
     let region_provider = RegionProviderChain::first_try(Region::new("eu-central-1"));
 
     let shared_config = aws_config::from_env().region(region_provider).load().await;
@@ -81,6 +129,8 @@ async fn main() {
         config,
         pool,
         s3_client,
+        states: Arc::new(RwLock::new(state_machine)),
+        new_events: Arc::new(watch::channel(()).0),
     };
 
     tracing_subscriber::fmt()
@@ -88,15 +138,57 @@ async fn main() {
         .pretty()
         .init();
 
+    if let Some(notification_config) = state.config.notifications.clone() {
+        tokio::spawn(notifications::run(state.clone(), notification_config));
+    }
+    tokio::spawn(webhooks::run(state.clone()));
+
     let public_routes = Router::new()
         .route("/projects", get(projects))
         .route("/projects/:id", get(project))
         .route("/projects/:id/works", get(project_works))
         .route("/events", get(events))
+        .route("/events/stream", get(event_stream))
         .route("/works", get(works))
+        .route("/works/stale", get(stale_works))
         .route("/works/:id", get(work))
         .route("/works/:id/events", get(work_events))
+        .route("/works/:id/reverted-events", get(reverted_events))
+        .route("/works/:id/shrinkage", get(work_shrinkage))
         .route("/clays", get(clays))
+        .route("/clays/:id", get(clay))
+        .route("/clays/:id/compatible-glazes", get(compatible_glazes))
+        .route("/clays/:id/shrinkage", get(shrinkage))
+        .route("/clays/:id/measured-shrinkage", get(measured_shrinkage))
+        .route("/clays/:id/stale-thresholds", get(stale_thresholds))
+        .route("/glazes", get(glazes))
+        .route("/glazes/:id", get(glaze))
+        .route("/glazes/:id/recipe", get(recipe))
+        .route("/glazes/:id/batch", get(batch))
+        .route("/glazes/:id/umf", get(glaze_umf))
+        .route("/glazes/:id/analysis", get(analysis))
+        .route("/materials", get(materials))
+        .route("/materials/:id", get(material))
+        .route("/umf", post(umf))
+        .route("/kilns", get(kilns))
+        .route("/kilns/:id", get(kiln))
+        .route("/kilns/:id/maintenance", get(maintenance))
+        .route("/kilns/:id/counters", get(counters))
+        .route("/kilns/maintenance-due", get(maintenance_due))
+        .route("/kiln-load", get(load_plan))
+        .route("/firings", get(firings))
+        .route("/firings/:id", get(firing))
+        .route("/firing-logs/:id", get(firing_log))
+        .route("/schedules", get(schedules))
+        .route("/schedules/:id", get(schedule))
+        .route("/schedules/:id/program", get(program_listing))
+        .route("/schedules/:id/controller", get(controller_program))
+        .route("/states", get(states))
+        .route("/workflows", get(workflows))
+        .route("/workflows/:id", get(workflow))
+        .route("/stats/defects", get(defects))
+        .route("/tags", get(tags))
+        .route("/search", get(search))
         .route("/login", post(login));
 
     let protected_routes = Router::new()
@@ -105,7 +197,50 @@ async fn main() {
         .route("/projects/:id", put(put_project).delete(delete_project))
         .route("/works", post(post_work))
         .route("/works/:id", put(put_work).delete(delete_work))
-        .route("/works/:id/state", put(put_state))
+        .route("/works/:id/state", put(put_state).delete(revert_state))
+        .route("/works/:id/chemistry", put(put_chemistry))
+        .route("/materials", post(post_material))
+        .route("/materials/:id", put(put_material).delete(delete_material))
+        .route("/clays", post(post_clay))
+        .route("/clays/:id", put(put_clay).delete(delete_clay))
+        .route("/clays/:id/stale-thresholds", put(put_stale_thresholds))
+        .route("/glazes", post(post_glaze))
+        .route("/glazes/:id", put(put_glaze).delete(delete_glaze))
+        .route("/glazes/:id/recipe", put(put_recipe))
+        .route("/glazes/:id/analysis", put(put_analysis))
+        .route("/kilns", post(post_kiln))
+        .route("/kilns/:id", put(put_kiln).delete(delete_kiln))
+        .route("/kilns/:id/maintenance", post(post_maintenance))
+        .route(
+            "/kilns/:id/maintenance/:maintenance_id",
+            delete(delete_maintenance),
+        )
+        .route("/firings", post(post_firing))
+        .route("/firings/:id", put(put_firing).delete(delete_firing))
+        .route("/firings/:id/works", put(put_works))
+        .route("/firings/:id/complete", post(post_complete))
+        .route("/firing-logs", post(post_firing_log))
+        .route("/firing-logs/:id", delete(delete_firing_log))
+        .route("/schedules", post(post_schedule))
+        .route("/schedules/:id", put(put_schedule).delete(delete_schedule))
+        .route("/states", post(post_state))
+        .route(
+            "/states/:id",
+            put(put_state_definition).delete(delete_state),
+        )
+        .route("/workflows", post(post_workflow))
+        .route("/workflows/:id", put(put_workflow).delete(delete_workflow))
+        .route("/notifications", get(notifications))
+        .route("/webhooks", get(webhooks).post(post_webhook))
+        .route(
+            "/webhooks/:id",
+            get(webhook).put(put_webhook).delete(delete_webhook),
+        )
+        .route("/webhooks/:id/deliveries", get(deliveries))
+        .route(
+            "/webhooks/:id/deliveries/:delivery_id/retry",
+            post(retry_delivery),
+        )
         .route("/upload", post(upload_image_to_s3))
         .layer(middleware::from_fn_with_state(state.clone(), auth));
 
@@ -126,3 +261,29 @@ async fn main() {
         .await
         .unwrap();
 }
+
+/// A fresh in-memory database with every migration run, for tests that need
+/// the schema and seed data.
+#[cfg(test)]
+pub(crate) async fn test_pool() -> SqlitePool {
+    let opts = SqliteConnectOptions::new()
+        .filename(":memory:")
+        .pragma("foreign_keys", "OFF");
+
+    // A single connection, as every connection to ":memory:" gets a
+    // database of its own.
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(opts)
+        .await
+        .expect("cannot connect to db");
+
+    sqlx::migrate!("db/migrations").run(&pool).await.unwrap();
+
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    pool
+}
@@ -1,5 +1,9 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use crate::config::MaintenanceConfig;
 
 #[derive(Serialize)]
 pub(crate) struct Clay {
@@ -7,70 +11,856 @@ pub(crate) struct Clay {
     pub(crate) name: String,
     pub(crate) description: Option<String>,
     pub(crate) shrinkage: f64,
+    /// Coefficient of thermal expansion, in 10^-7 per degree Celsius.
+    pub(crate) coe: Option<f64>,
+    pub(crate) supplier: Option<String>,
+    pub(crate) cone_min: Option<String>,
+    pub(crate) cone_max: Option<String>,
+    pub(crate) fired_colour: Option<String>,
+    /// Fraction of the body that is grog.
+    pub(crate) grog: Option<f64>,
+    pub(crate) is_active: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutClay {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) shrinkage: f64,
+    pub(crate) coe: Option<f64>,
+    pub(crate) supplier: Option<String>,
+    pub(crate) cone_min: Option<String>,
+    pub(crate) cone_max: Option<String>,
+    pub(crate) fired_colour: Option<String>,
+    pub(crate) grog: Option<f64>,
+    pub(crate) is_active: bool,
+}
+
+/// Position of an Orton cone on the temperature scale, hottest highest.
+/// Cones written with a leading zero, such as 06, are colder than cone 1 and
+/// get colder as their number grows.
+pub(crate) fn cone_rank(cone: &str) -> Option<i32> {
+    let cone = cone.trim();
+    let number = cone.parse::<i32>().ok().filter(|n| *n >= 0)?;
+    if cone.len() > 1 && cone.starts_with('0') {
+        Some(-number)
+    } else {
+        Some(number)
+    }
+}
+
+/// Size of a piece once thrown, given its size after firing and the linear
+/// shrinkage fraction of its clay.
+pub(crate) fn wet_size(fired: f64, shrinkage: f64) -> f64 {
+    fired / (1.0 - shrinkage)
+}
+
+/// Size of a piece after firing, given its size once thrown and the linear
+/// shrinkage fraction of its clay.
+pub(crate) fn fired_size(wet: f64, shrinkage: f64) -> f64 {
+    wet * (1.0 - shrinkage)
+}
+
+/// Volume shrinks in all three dimensions, so scales with the cube of the
+/// linear shrinkage.
+pub(crate) fn wet_volume(fired: f64, shrinkage: f64) -> f64 {
+    fired / (1.0 - shrinkage).powi(3)
+}
+
+pub(crate) fn fired_volume(wet: f64, shrinkage: f64) -> f64 {
+    wet * (1.0 - shrinkage).powi(3)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LengthUnit {
+    Mm,
+    #[default]
+    Cm,
+    In,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum VolumeUnit {
+    #[default]
+    Ml,
+    L,
+    FlOz,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Dimensions {
+    pub(crate) height: Option<f64>,
+    pub(crate) diameter: Option<f64>,
+    pub(crate) volume: Option<f64>,
+}
+
+/// Wet and fired dimensions of a piece. Shrinkage is proportional, so both
+/// are given in the units the dimensions were requested in.
+#[derive(Serialize)]
+pub(crate) struct ShrinkageCalculation {
+    pub(crate) shrinkage: f64,
+    pub(crate) unit: LengthUnit,
+    pub(crate) volume_unit: VolumeUnit,
+    pub(crate) wet: Dimensions,
+    pub(crate) fired: Dimensions,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, sqlx::Type)]
+pub(crate) enum Surface {
+    Gloss,
+    Matte,
+    Satin,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Glaze {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) cone_min: Option<String>,
+    pub(crate) cone_max: Option<String>,
+    pub(crate) surface: Option<Surface>,
+    /// Estimated coefficient of thermal expansion, in 10^-7 per degree
+    /// Celsius, calculated from the glaze's oxide analysis.
+    pub(crate) coe: Option<f64>,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone, Copy)]
+pub(crate) enum GlazeFit {
+    Ok,
+    CrazingRisk,
+    ShiveringRisk,
+}
+
+/// Compares the expansion of a glaze against the clay body it sits on. A
+/// glaze that expands more than the body is put in tension on cooling and
+/// crazes, whereas one that expands far less is put in so much compression
+/// that it can shiver off.
+pub(crate) fn glaze_fit(clay_coe: f64, glaze_coe: f64) -> GlazeFit {
+    let ratio = glaze_coe / clay_coe;
+    if ratio > 1.0 {
+        GlazeFit::CrazingRisk
+    } else if ratio < 0.85 {
+        GlazeFit::ShiveringRisk
+    } else {
+        GlazeFit::Ok
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutGlaze {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) cone_min: Option<String>,
+    pub(crate) cone_max: Option<String>,
+    pub(crate) surface: Option<Surface>,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
-pub(crate) enum State {
-    Thrown,
-    Trimming,
-    Handbuilt,
-    AwaitingBisqueFiring,
-    AwaitingGlazeFiring,
-    Finished,
-    Recycled,
-    Unknown,
-}
-
-impl From<i32> for State {
-    fn from(id: i32) -> Self {
-        match id {
-            1 => State::Thrown,
-            2 => State::Trimming,
-            3 => State::Handbuilt,
-            4 => State::AwaitingBisqueFiring,
-            5 => State::AwaitingGlazeFiring,
-            6 => State::Finished,
-            7 => State::Recycled,
-            _ => State::Unknown,
+pub(crate) struct Ingredient {
+    pub(crate) material: String,
+    pub(crate) percentage: f64,
+}
+
+/// A glaze recipe. The base ingredients make up 100% of the batch, while
+/// additions (colourants, opacifiers, ...) are expressed as a percentage on
+/// top of the base.
+#[derive(Deserialize, Serialize, Debug)]
+pub(crate) struct Recipe {
+    pub(crate) ingredients: Vec<Ingredient>,
+    #[serde(default)]
+    pub(crate) additions: Vec<Ingredient>,
+}
+
+impl Recipe {
+    pub(crate) fn base_total(&self) -> f64 {
+        self.ingredients.iter().map(|i| i.percentage).sum()
+    }
+
+    /// The first ingredient or addition with a negative or non-finite
+    /// percentage, if any.
+    pub(crate) fn invalid_ingredient(&self) -> Option<&Ingredient> {
+        self.ingredients
+            .iter()
+            .chain(self.additions.iter())
+            .find(|i| !is_valid_percentage(i.percentage))
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.invalid_ingredient().is_none() && (self.base_total() - 100.0).abs() < 0.01
+    }
+
+    pub(crate) fn batch(&self, grams: f64) -> Batch {
+        let scale = |ingredients: &[Ingredient]| {
+            ingredients
+                .iter()
+                .map(|i| BatchIngredient {
+                    material: i.material.clone(),
+                    percentage: i.percentage,
+                    grams: grams * i.percentage / 100.0,
+                })
+                .collect::<Vec<BatchIngredient>>()
+        };
+
+        let ingredients = scale(&self.ingredients);
+        let additions = scale(&self.additions);
+        let total_grams = ingredients
+            .iter()
+            .chain(additions.iter())
+            .map(|i| i.grams)
+            .sum();
+
+        Batch {
+            grams,
+            total_grams,
+            ingredients,
+            additions,
         }
     }
 }
 
-impl From<State> for i32 {
-    fn from(state: State) -> Self {
-        match state {
-            State::Thrown => 1,
-            State::Trimming => 2,
-            State::Handbuilt => 3,
-            State::AwaitingBisqueFiring => 4,
-            State::AwaitingGlazeFiring => 5,
-            State::Finished => 6,
-            State::Recycled => 7,
-            State::Unknown => 0,
+#[derive(Serialize)]
+pub(crate) struct BatchIngredient {
+    pub(crate) material: String,
+    pub(crate) percentage: f64,
+    pub(crate) grams: f64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Batch {
+    pub(crate) grams: f64,
+    pub(crate) total_grams: f64,
+    pub(crate) ingredients: Vec<BatchIngredient>,
+    pub(crate) additions: Vec<BatchIngredient>,
+}
+
+#[derive(
+    Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, sqlx::Type,
+)]
+pub(crate) enum Oxide {
+    SiO2,
+    TiO2,
+    Al2O3,
+    B2O3,
+    Fe2O3,
+    Li2O,
+    Na2O,
+    K2O,
+    MgO,
+    CaO,
+    SrO,
+    BaO,
+    ZnO,
+}
+
+/// Weight percentages of each oxide in a material or glaze.
+pub(crate) type OxideAnalysis = BTreeMap<Oxide, f64>;
+
+/// Whether a percentage of a recipe or analysis can be calculated with.
+pub(crate) fn is_valid_percentage(percentage: f64) -> bool {
+    percentage.is_finite() && percentage >= 0.0
+}
+
+/// The first oxide of an analysis with a negative or non-finite percentage,
+/// if any.
+pub(crate) fn invalid_oxide(analysis: &OxideAnalysis) -> Option<Oxide> {
+    analysis
+        .iter()
+        .find(|(_, percentage)| !is_valid_percentage(**percentage))
+        .map(|(oxide, _)| *oxide)
+}
+
+#[derive(Serialize)]
+pub(crate) struct Material {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) analysis: OxideAnalysis,
+    pub(crate) loi: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutMaterial {
+    pub(crate) name: String,
+    pub(crate) analysis: OxideAnalysis,
+    pub(crate) loi: f64,
+}
+
+/// A Seger/Unity Molecular Formula, with every oxide expressed as moles
+/// relative to one mole of flux.
+#[derive(Deserialize, Serialize, Debug)]
+pub(crate) struct Umf {
+    pub(crate) oxides: BTreeMap<Oxide, f64>,
+    pub(crate) r2o: f64,
+    pub(crate) ro: f64,
+    pub(crate) si_al_ratio: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GlazeChemistry {
+    pub(crate) recipe: Recipe,
+    pub(crate) umf: Umf,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, sqlx::Type)]
+pub(crate) enum ApplicationMethod {
+    Dip,
+    Pour,
+    Brush,
+    Spray,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GlazeLayer {
+    pub(crate) glaze: Glaze,
+    pub(crate) method: Option<ApplicationMethod>,
+    pub(crate) coats: i32,
+    pub(crate) area: Option<String>,
+    pub(crate) fit: Option<GlazeFit>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutGlazeLayer {
+    pub(crate) glaze_id: i32,
+    pub(crate) method: Option<ApplicationMethod>,
+    pub(crate) coats: i32,
+    pub(crate) area: Option<String>,
+}
+
+/// A state a work can be in, identified by its key in the `states` table.
+/// States are data, but the built in ones below are relied on by firings,
+/// shrinkage and recycling.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Debug, Clone)]
+#[serde(transparent)]
+pub(crate) struct State(Cow<'static, str>);
+
+impl State {
+    pub(crate) const THROWN: State = State(Cow::Borrowed("Thrown"));
+    pub(crate) const TRIMMING: State = State(Cow::Borrowed("Trimming"));
+    pub(crate) const HANDBUILT: State = State(Cow::Borrowed("Handbuilt"));
+    pub(crate) const AWAITING_BISQUE_FIRING: State = State(Cow::Borrowed("AwaitingBisqueFiring"));
+    pub(crate) const AWAITING_GLAZE_FIRING: State = State(Cow::Borrowed("AwaitingGlazeFiring"));
+    pub(crate) const FINISHED: State = State(Cow::Borrowed("Finished"));
+    pub(crate) const RECYCLED: State = State(Cow::Borrowed("Recycled"));
+    pub(crate) const FAILED_IN_FIRING: State = State(Cow::Borrowed("FailedInFiring"));
+
+    pub(crate) const BUILT_IN: [State; 8] = [
+        State::THROWN,
+        State::TRIMMING,
+        State::HANDBUILT,
+        State::AWAITING_BISQUE_FIRING,
+        State::AWAITING_GLAZE_FIRING,
+        State::FINISHED,
+        State::RECYCLED,
+        State::FAILED_IN_FIRING,
+    ];
+
+    pub(crate) fn key(&self) -> &str {
+        &self.0
+    }
+
+    pub(crate) fn is_built_in(&self) -> bool {
+        State::BUILT_IN.contains(self)
+    }
+
+    /// Whether works in this state have been lost, and so may record a defect.
+    pub(crate) fn is_failure(&self) -> bool {
+        *self == State::RECYCLED || *self == State::FAILED_IN_FIRING
+    }
+}
+
+impl From<String> for State {
+    fn from(key: String) -> Self {
+        State(Cow::Owned(key))
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct StateDefinition {
+    pub(crate) id: i32,
+    pub(crate) key: State,
+    pub(crate) name: String,
+    pub(crate) is_built_in: bool,
+    /// Days works may stay in this state before they are stale, if ever.
+    pub(crate) max_days: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutStateDefinition {
+    pub(crate) key: State,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) max_days: Option<f64>,
+}
+
+/// Days works of a clay may stay in a state, overriding the state's own.
+#[derive(Deserialize, Serialize, Debug)]
+pub(crate) struct StateThreshold {
+    pub(crate) state: State,
+    pub(crate) max_days: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Debug, Clone)]
+pub(crate) struct StateTransition {
+    pub(crate) from: State,
+    pub(crate) to: State,
+}
+
+/// A profile a work is made under, such as raku or single firing, with the
+/// transitions allowed between states for works following it.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct Workflow {
+    pub(crate) id: i32,
+    pub(crate) key: String,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) transitions: Vec<StateTransition>,
+}
+
+impl Workflow {
+    pub(crate) fn is_valid_transition(
+        &self,
+        previous_state: &State,
+        current_state: &State,
+    ) -> bool {
+        self.transitions
+            .iter()
+            .any(|t| &t.from == previous_state && &t.to == current_state)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutWorkflow {
+    pub(crate) key: String,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) transitions: Vec<StateTransition>,
+}
+
+/// The states works can be in and the workflows they move between them by,
+/// as loaded from the database.
+#[derive(Clone, Default)]
+pub(crate) struct StateMachine {
+    states: Vec<StateDefinition>,
+    workflows: Vec<Workflow>,
+}
+
+impl StateMachine {
+    /// Workflow works are made under unless another is chosen.
+    pub(crate) const DEFAULT_WORKFLOW: &'static str = "Standard";
+
+    pub(crate) fn new(states: Vec<StateDefinition>, workflows: Vec<Workflow>) -> Self {
+        StateMachine { states, workflows }
+    }
+
+    pub(crate) fn states(&self) -> &[StateDefinition] {
+        &self.states
+    }
+
+    pub(crate) fn workflows(&self) -> &[Workflow] {
+        &self.workflows
+    }
+
+    pub(crate) fn id(&self, state: &State) -> Option<i32> {
+        self.states.iter().find(|s| &s.key == state).map(|s| s.id)
+    }
+
+    pub(crate) fn workflow(&self, key: &str) -> Option<&Workflow> {
+        self.workflows.iter().find(|w| w.key == key)
+    }
+
+    pub(crate) fn is_valid_transition(
+        &self,
+        workflow_id: i32,
+        previous_state: &State,
+        current_state: &State,
+    ) -> bool {
+        self.workflows
+            .iter()
+            .find(|w| w.id == workflow_id)
+            .is_some_and(|w| w.is_valid_transition(previous_state, current_state))
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum FiringType {
+    Bisque,
+    Glaze,
+}
+
+impl FiringType {
+    /// The state works are in when loaded into this type of firing, and the
+    /// state they are in once it completes.
+    pub(crate) fn transition(&self) -> (State, State) {
+        match self {
+            FiringType::Bisque => (State::AWAITING_BISQUE_FIRING, State::AWAITING_GLAZE_FIRING),
+            FiringType::Glaze => (State::AWAITING_GLAZE_FIRING, State::FINISHED),
+        }
+    }
+}
+
+/// Temperature kilns are assumed to start firing from, in degrees Celsius.
+pub(crate) const ROOM_TEMPERATURE: f64 = 20.0;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub(crate) struct Segment {
+    /// Degrees Celsius per hour.
+    pub(crate) ramp_rate: f64,
+    /// Degrees Celsius.
+    pub(crate) target_temperature: f64,
+    pub(crate) hold_minutes: i32,
+}
+
+impl Segment {
+    /// Whether the segment can be fired: ramping at some rate, whether
+    /// heating or cooling, to a real temperature, and holding for no time
+    /// or more.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.ramp_rate.is_finite()
+            && self.ramp_rate > 0.0
+            && self.target_temperature.is_finite()
+            && self.hold_minutes >= 0
+    }
+}
+
+/// Expected duration of a firing in minutes, from room temperature through
+/// each ramp and hold. Natural cooling after the last segment isn't counted.
+pub(crate) fn schedule_duration(segments: &[Segment]) -> f64 {
+    let mut temperature = ROOM_TEMPERATURE;
+    let mut minutes = 0.0;
+    for segment in segments {
+        minutes += (segment.target_temperature - temperature).abs() / segment.ramp_rate * 60.0;
+        minutes += segment.hold_minutes as f64;
+        temperature = segment.target_temperature;
+    }
+    minutes
+}
+
+#[derive(Serialize)]
+pub(crate) struct FiringSchedule {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) segments: Vec<Segment>,
+    pub(crate) duration_minutes: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutFiringSchedule {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) segments: Vec<Segment>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ControllerSegment {
+    pub(crate) segment: usize,
+    pub(crate) rate: f64,
+    pub(crate) temperature: f64,
+    /// Hold time as HH:MM, as most controllers take it.
+    pub(crate) hold: String,
+}
+
+/// A firing schedule laid out the way kiln controllers are programmed.
+#[derive(Serialize)]
+pub(crate) struct ControllerProgram {
+    pub(crate) name: String,
+    pub(crate) temperature_unit: String,
+    pub(crate) segments: Vec<ControllerSegment>,
+    pub(crate) duration_minutes: f64,
+}
+
+impl From<&FiringSchedule> for ControllerProgram {
+    fn from(schedule: &FiringSchedule) -> Self {
+        let segments = schedule
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| ControllerSegment {
+                segment: i + 1,
+                rate: s.ramp_rate,
+                temperature: s.target_temperature,
+                hold: format!("{:02}:{:02}", s.hold_minutes / 60, s.hold_minutes % 60),
+            })
+            .collect::<Vec<ControllerSegment>>();
+
+        ControllerProgram {
+            name: schedule.name.clone(),
+            temperature_unit: "C".to_string(),
+            segments,
+            duration_minutes: schedule.duration_minutes,
         }
     }
 }
 
-pub(crate) fn is_valid_transition(previous_state: State, current_state: State) -> bool {
-    match (previous_state, current_state) {
-        (State::Thrown, State::Trimming) => true,
-        (State::Trimming, State::AwaitingBisqueFiring) => true,
-        (State::Handbuilt, State::AwaitingBisqueFiring) => true,
-        (State::AwaitingBisqueFiring, State::AwaitingGlazeFiring) => true,
-        (State::AwaitingGlazeFiring, State::Finished) => true,
-        (State::Recycled, State::Thrown) => true,
-        (State::Recycled, State::Handbuilt) => true,
-        (previous, State::Recycled) => previous != State::Finished && previous != State::Unknown,
-        _ => false,
+impl std::fmt::Display for ControllerProgram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(
+            f,
+            "{:<4} {:>12} {:>12} {:>6}",
+            "SEG", "RATE (C/h)", "TEMP (C)", "HOLD"
+        )?;
+        for s in &self.segments {
+            writeln!(
+                f,
+                "{:<4} {:>12} {:>12} {:>6}",
+                s.segment, s.rate, s.temperature, s.hold
+            )?;
+        }
+        let minutes = self.duration_minutes.round() as i64;
+        writeln!(f, "Total: {}h {:02}m", minutes / 60, minutes % 60)
     }
 }
 
+#[derive(Serialize)]
+pub(crate) struct Kiln {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutKiln {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum MaintenanceKind {
+    ElementReplacement,
+    ThermocoupleChange,
+    ShelfWash,
+    RelaySwap,
+}
+
+impl MaintenanceKind {
+    pub(crate) const ALL: [MaintenanceKind; 4] = [
+        MaintenanceKind::ElementReplacement,
+        MaintenanceKind::ThermocoupleChange,
+        MaintenanceKind::ShelfWash,
+        MaintenanceKind::RelaySwap,
+    ];
+
+    pub(crate) fn threshold(&self, config: &MaintenanceConfig) -> usize {
+        match self {
+            MaintenanceKind::ElementReplacement => config.element_replacement,
+            MaintenanceKind::ThermocoupleChange => config.thermocouple_change,
+            MaintenanceKind::ShelfWash => config.shelf_wash,
+            MaintenanceKind::RelaySwap => config.relay_swap,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct Maintenance {
+    pub(crate) id: i32,
+    pub(crate) kiln: ApiResourceReference,
+    pub(crate) kind: MaintenanceKind,
+    pub(crate) performed_at: NaiveDateTime,
+    pub(crate) notes: Option<String>,
+    pub(crate) created_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutMaintenance {
+    pub(crate) kind: MaintenanceKind,
+    /// Defaults to now.
+    pub(crate) performed_at: Option<NaiveDateTime>,
+    pub(crate) notes: Option<String>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub(crate) struct MaintenanceCounter {
+    pub(crate) kind: MaintenanceKind,
+    pub(crate) last_performed_at: Option<NaiveDateTime>,
+    pub(crate) firings_since: usize,
+    pub(crate) threshold: usize,
+    pub(crate) is_due: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct KilnCounters {
+    pub(crate) kiln: ApiResourceReference,
+    pub(crate) firings: usize,
+    pub(crate) counters: Vec<MaintenanceCounter>,
+}
+
+/// Counts the firings since each kind of maintenance was last performed,
+/// given when each firing of a kiln happened and its maintenance history.
+pub(crate) fn maintenance_counters(
+    fired_at: &[NaiveDateTime],
+    history: &[(MaintenanceKind, NaiveDateTime)],
+    config: &MaintenanceConfig,
+) -> Vec<MaintenanceCounter> {
+    MaintenanceKind::ALL
+        .iter()
+        .map(|kind| {
+            let last_performed_at = history
+                .iter()
+                .filter(|(k, _)| k == kind)
+                .map(|(_, performed_at)| *performed_at)
+                .max();
+            let firings_since = fired_at
+                .iter()
+                .filter(|f| last_performed_at.is_none_or(|last| **f > last))
+                .count();
+            let threshold = kind.threshold(config);
+
+            MaintenanceCounter {
+                kind: *kind,
+                last_performed_at,
+                firings_since,
+                threshold,
+                is_due: firings_since >= threshold,
+            }
+        })
+        .collect()
+}
+
+/// A work loaded into a firing that came out of it broken.
+#[derive(Deserialize, Debug)]
+pub(crate) struct FiringFailure {
+    pub(crate) work_id: i32,
+    pub(crate) defect: Defect,
+    pub(crate) note: Option<String>,
+}
+
+/// Body of a firing completion request. Works listed as failures move to
+/// `FailedInFiring` rather than the state that follows the firing.
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct PostComplete {
+    #[serde(default)]
+    pub(crate) failures: Vec<FiringFailure>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Firing {
+    pub(crate) id: i32,
+    pub(crate) kiln: ApiResourceReference,
+    pub(crate) firing_type: FiringType,
+    pub(crate) target_cone: Option<String>,
+    pub(crate) started_at: Option<NaiveDateTime>,
+    pub(crate) ended_at: Option<NaiveDateTime>,
+    pub(crate) notes: Option<String>,
+    pub(crate) is_completed: bool,
+    pub(crate) schedule: Option<ApiResourceReference>,
+    pub(crate) works: Vec<ApiResourceReference>,
+    pub(crate) created_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutFiring {
+    pub(crate) kiln_id: i32,
+    pub(crate) firing_type: FiringType,
+    pub(crate) target_cone: Option<String>,
+    pub(crate) started_at: Option<NaiveDateTime>,
+    pub(crate) ended_at: Option<NaiveDateTime>,
+    pub(crate) notes: Option<String>,
+    pub(crate) schedule_id: Option<i32>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct TemperaturePoint {
+    pub(crate) time: NaiveDateTime,
+    /// Degrees Celsius.
+    pub(crate) temperature: f64,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone, Copy)]
+pub(crate) enum Trend {
+    Rising,
+    Holding,
+    Falling,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct LogSegment {
+    pub(crate) trend: Trend,
+    pub(crate) started_at: NaiveDateTime,
+    pub(crate) ended_at: NaiveDateTime,
+    pub(crate) start_temperature: f64,
+    pub(crate) end_temperature: f64,
+    /// Degrees Celsius per hour, negative when cooling.
+    pub(crate) rate: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct FiringLogStatistics {
+    pub(crate) peak_temperature: f64,
+    pub(crate) peak_at: NaiveDateTime,
+    pub(crate) minutes_above_1000: f64,
+    /// Average degrees Celsius per hour lost between the peak and the end of
+    /// the log.
+    pub(crate) cooling_rate: Option<f64>,
+    pub(crate) segments: Vec<LogSegment>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ZoneSeries {
+    pub(crate) name: String,
+    pub(crate) points: Vec<TemperaturePoint>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct FiringLog {
+    pub(crate) id: i32,
+    pub(crate) name: Option<String>,
+    pub(crate) firing: Option<ApiResourceReference>,
+    pub(crate) started_at: NaiveDateTime,
+    pub(crate) ended_at: NaiveDateTime,
+    pub(crate) works: Vec<ApiResourceReference>,
+    pub(crate) zones: Vec<ZoneSeries>,
+    /// Derived from the mean temperature across every zone.
+    pub(crate) statistics: Option<FiringLogStatistics>,
+    pub(crate) created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub(crate) struct PlacedWork {
+    pub(crate) work: ApiResourceReference,
+    /// Offset from the front left corner of the shelf, in centimetres.
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) width: f64,
+    pub(crate) depth: f64,
+    pub(crate) height: f64,
+    /// Whether the work is turned a quarter from its recorded footprint.
+    pub(crate) rotated: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ShelfLoad {
+    /// Counted from the bottom of the kiln, starting at 1.
+    pub(crate) position: usize,
+    /// Height of the shelf's surface above the kiln floor.
+    pub(crate) elevation: f64,
+    /// Height of the posts above this shelf, which the works must clear.
+    pub(crate) post_height: f64,
+    /// Fraction of the shelf's area covered by works.
+    pub(crate) utilisation: f64,
+    pub(crate) works: Vec<PlacedWork>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct KilnLoad {
+    pub(crate) firing_type: FiringType,
+    pub(crate) shelves: Vec<ShelfLoad>,
+    /// Works that didn't fit, left for the next firing.
+    pub(crate) leftover: Vec<ApiResourceReference>,
+    /// Works without a recorded footprint or height, which can't be planned.
+    pub(crate) unmeasured: Vec<ApiResourceReference>,
+}
+
 #[derive(Serialize)]
 pub(crate) struct Project {
     pub(crate) id: i32,
     pub(crate) name: String,
     pub(crate) description: Option<String>,
     pub(crate) images: Images,
+    pub(crate) tags: Vec<String>,
     pub(crate) created_at: NaiveDateTime,
 }
 
@@ -79,6 +869,39 @@ pub(crate) struct PutProject {
     pub(crate) name: String,
     pub(crate) description: Option<String>,
     pub(crate) thumbnail: Option<String>,
+    /// Replaces the tags of the project, unchanged if not given.
+    pub(crate) tags: Option<Vec<String>>,
+}
+
+/// How many works and projects a tag is on.
+#[derive(Serialize)]
+pub(crate) struct TagUsage {
+    pub(crate) name: String,
+    pub(crate) works: i32,
+    pub(crate) projects: i32,
+}
+
+/// Whether a resource must have all of the tags filtered by, or any one of
+/// them.
+#[derive(Deserialize, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TagMatch {
+    #[default]
+    All,
+    Any,
+}
+
+/// Tags as they are stored: trimmed, lower case, without blanks or
+/// duplicates, and sorted.
+pub(crate) fn normalise_tags(tags: &[String]) -> Vec<String> {
+    let mut tags = tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect::<Vec<String>>();
+    tags.sort();
+    tags.dedup();
+    tags
 }
 
 #[derive(Serialize)]
@@ -88,6 +911,11 @@ pub(crate) struct ApiResourceReference {
 }
 
 pub(crate) enum ApiResource {
+    Clay,
+    Firing,
+    Glaze,
+    Kiln,
+    Schedule,
     Project,
     Work,
 }
@@ -96,6 +924,11 @@ impl From<(ApiResource, i32)> for ApiResourceReference {
     fn from(item: (ApiResource, i32)) -> Self {
         let (resource, id) = item;
         let url = match resource {
+            ApiResource::Clay => format!("/clays/{}", id),
+            ApiResource::Firing => format!("/firings/{}", id),
+            ApiResource::Glaze => format!("/glazes/{}", id),
+            ApiResource::Kiln => format!("/kilns/{}", id),
+            ApiResource::Schedule => format!("/schedules/{}", id),
             ApiResource::Project => format!("/projects/{}", id),
             ApiResource::Work => format!("/works/{}", id),
         };
@@ -110,6 +943,36 @@ pub(crate) struct CurrentState {
     pub(crate) transitioned_at: NaiveDateTime,
 }
 
+/// A work left in its current state for longer than it should be.
+#[derive(Serialize)]
+pub(crate) struct StaleWork {
+    pub(crate) work: ApiResourceReference,
+    pub(crate) name: String,
+    pub(crate) clay: ApiResourceReference,
+    pub(crate) current_state: CurrentState,
+    pub(crate) days_in_state: f64,
+    pub(crate) max_days: f64,
+    pub(crate) days_overdue: f64,
+}
+
+/// Whether works may be left in a state for this many days before they are
+/// stale.
+pub(crate) fn is_valid_max_days(max_days: f64) -> bool {
+    max_days.is_finite() && max_days > 0.0
+}
+
+/// Days a work has been in its current state, and the days by which that
+/// exceeds its threshold, if it does.
+pub(crate) fn staleness(
+    transitioned_at: NaiveDateTime,
+    now: NaiveDateTime,
+    max_days: f64,
+) -> Option<(f64, f64)> {
+    let days_in_state = (now - transitioned_at).num_seconds() as f64 / 86400.0;
+    let days_overdue = days_in_state - max_days;
+    (days_overdue > 0.0).then_some((days_in_state, days_overdue))
+}
+
 #[derive(Serialize)]
 pub(crate) struct Work {
     pub(crate) id: i32,
@@ -118,10 +981,20 @@ pub(crate) struct Work {
     pub(crate) notes: Option<String>,
     pub(crate) clay: Clay,
     pub(crate) current_state: CurrentState,
+    pub(crate) glazes: Vec<GlazeLayer>,
     pub(crate) glaze_description: Option<String>,
+    pub(crate) glaze_chemistry: Option<GlazeChemistry>,
     pub(crate) images: Images,
     pub(crate) created_at: NaiveDateTime,
     pub(crate) is_multiple: bool,
+    /// Centimetres taken up on a kiln shelf.
+    pub(crate) footprint_width_cm: Option<f64>,
+    pub(crate) footprint_depth_cm: Option<f64>,
+    pub(crate) height_cm: Option<f64>,
+    pub(crate) workflow: String,
+    /// Times the work has come out of a kiln, counting refires.
+    pub(crate) firing_count: i32,
+    pub(crate) tags: Vec<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -130,10 +1003,18 @@ pub(crate) struct PutWork {
     pub(crate) name: String,
     pub(crate) notes: Option<String>,
     pub(crate) clay_id: i32,
+    pub(crate) glazes: Option<Vec<PutGlazeLayer>>,
     pub(crate) glaze_description: Option<String>,
     pub(crate) thumbnail: Option<String>,
     pub(crate) header: Option<String>,
     pub(crate) is_multiple: bool,
+    pub(crate) footprint_width_cm: Option<f64>,
+    pub(crate) footprint_depth_cm: Option<f64>,
+    pub(crate) height_cm: Option<f64>,
+    /// Key of the workflow to follow from now on, unchanged if not given.
+    pub(crate) workflow: Option<String>,
+    /// Replaces the tags of the work, unchanged if not given.
+    pub(crate) tags: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -142,11 +1023,21 @@ pub(crate) struct PostWork {
     pub(crate) name: String,
     pub(crate) notes: Option<String>,
     pub(crate) clay_id: i32,
+    #[serde(default)]
+    pub(crate) glazes: Vec<PutGlazeLayer>,
     pub(crate) glaze_description: Option<String>,
     pub(crate) state: State,
+    pub(crate) measurements: Option<Measurements>,
     pub(crate) thumbnail: Option<String>,
     pub(crate) header: Option<String>,
     pub(crate) is_multiple: bool,
+    pub(crate) footprint_width_cm: Option<f64>,
+    pub(crate) footprint_depth_cm: Option<f64>,
+    pub(crate) height_cm: Option<f64>,
+    /// Key of the workflow to follow, the default if not given.
+    pub(crate) workflow: Option<String>,
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -155,15 +1046,360 @@ pub(crate) struct Images {
     pub(crate) thumbnail: Option<String>,
 }
 
+/// Measurements of a work taken as it transitions state, in millimetres and
+/// grams.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub(crate) struct Measurements {
+    pub(crate) height: Option<f64>,
+    pub(crate) width: Option<f64>,
+    pub(crate) weight: Option<f64>,
+    pub(crate) wall_thickness: Option<f64>,
+}
+
+impl Measurements {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.height.is_none()
+            && self.width.is_none()
+            && self.weight.is_none()
+            && self.wall_thickness.is_none()
+    }
+}
+
+/// Why a work was lost.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum Defect {
+    SCrack,
+    Warping,
+    BlowOut,
+    Crawling,
+    Pinholing,
+    Crazing,
+    Shivering,
+    CollapsedOnWheel,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Transition {
+    pub(crate) state: State,
+    pub(crate) measurements: Option<Measurements>,
+    /// The firing schedule used, when transitioning out of a firing.
+    pub(crate) schedule_id: Option<i32>,
+    /// The kiln fired in, when transitioning out of a firing.
+    pub(crate) kiln_id: Option<i32>,
+    /// Why the work was lost, when recycling it or failing it in a firing.
+    pub(crate) defect: Option<Defect>,
+    pub(crate) defect_note: Option<String>,
+    /// What was done to the work, for its making journal.
+    pub(crate) note: Option<String>,
+    /// Keys of photos taken of the work as it transitioned.
+    #[serde(default)]
+    pub(crate) images: Vec<String>,
+    /// When the transition happened, if before it is recorded. Must not be
+    /// before the work's previous transition.
+    pub(crate) occurred_at: Option<NaiveDateTime>,
+}
+
+impl Transition {
+    /// Whether a defect is only given when the work is lost.
+    pub(crate) fn is_valid_defect(&self) -> bool {
+        self.state.is_failure() || (self.defect.is_none() && self.defect_note.is_none())
+    }
+}
+
+/// Body of a state transition request, which is either the bare state to
+/// transition to or a `Transition` carrying extra detail.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum PutState {
+    State(State),
+    Transition(Transition),
+}
+
+impl From<PutState> for Transition {
+    fn from(put_state: PutState) -> Self {
+        match put_state {
+            PutState::State(state) => Transition {
+                state,
+                measurements: None,
+                schedule_id: None,
+                kiln_id: None,
+                defect: None,
+                defect_note: None,
+                note: None,
+                images: Vec::new(),
+                occurred_at: None,
+            },
+            PutState::Transition(transition) => transition,
+        }
+    }
+}
+
+/// Linear shrinkage of a work, as fractions, measured between it being formed
+/// and finished.
+#[derive(Serialize, PartialEq, Debug)]
+pub(crate) struct MeasuredShrinkage {
+    pub(crate) height: Option<f64>,
+    pub(crate) width: Option<f64>,
+}
+
+impl MeasuredShrinkage {
+    pub(crate) fn mean(&self) -> Option<f64> {
+        let values = [self.height, self.width]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<f64>>();
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().sum::<f64>() / values.len() as f64)
+        }
+    }
+}
+
+/// Calculates the shrinkage of a work from its chronologically ordered
+/// events, comparing the latest measurements taken while Thrown or Handbuilt
+/// with those taken when Finished.
+pub(crate) fn work_shrinkage(events: &[Event]) -> Option<MeasuredShrinkage> {
+    let latest_measured = |states: &[State]| {
+        events
+            .iter()
+            .rev()
+            .filter(|e| states.contains(&e.current_state))
+            .find_map(|e| e.measurements.as_ref())
+    };
+
+    let formed = latest_measured(&[State::THROWN, State::HANDBUILT])?;
+    let finished = latest_measured(&[State::FINISHED])?;
+    let shrinkage = |wet: Option<f64>, fired: Option<f64>| {
+        wet.zip(fired)
+            .filter(|(wet, _)| *wet > 0.0)
+            .map(|(wet, fired)| 1.0 - fired / wet)
+    };
+
+    let measured = MeasuredShrinkage {
+        height: shrinkage(formed.height, finished.height),
+        width: shrinkage(formed.width, finished.width),
+    };
+    measured.mean().map(|_| measured)
+}
+
+/// Shrinkage of a clay measured across its finished works, compared with the
+/// nominal shrinkage of the clay.
+#[derive(Serialize)]
+pub(crate) struct ClayShrinkage {
+    pub(crate) clay: ApiResourceReference,
+    pub(crate) nominal: f64,
+    pub(crate) measured: Option<f64>,
+    pub(crate) difference: Option<f64>,
+    pub(crate) samples: usize,
+}
+
 #[derive(Serialize)]
 pub(crate) struct Event {
     pub(crate) id: i32,
     pub(crate) work: ApiResourceReference,
     pub(crate) previous_state: Option<State>,
     pub(crate) current_state: State,
+    /// When the transition happened, which events are ordered by.
+    pub(crate) occurred_at: NaiveDateTime,
+    /// When the transition was recorded.
+    pub(crate) created_at: NaiveDateTime,
+    pub(crate) measurements: Option<Measurements>,
+    pub(crate) firing: Option<ApiResourceReference>,
+    pub(crate) schedule: Option<ApiResourceReference>,
+    pub(crate) kiln: Option<ApiResourceReference>,
+    pub(crate) defect: Option<Defect>,
+    pub(crate) defect_note: Option<String>,
+    pub(crate) note: Option<String>,
+    pub(crate) images: Vec<String>,
+}
+
+/// A transition that was undone, as it was before being removed.
+#[derive(Serialize)]
+pub(crate) struct RevertedEvent {
+    pub(crate) event: serde_json::Value,
+    pub(crate) reverted_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DefectCount {
+    /// `None` for failures recorded without a defect.
+    pub(crate) defect: Option<Defect>,
+    pub(crate) count: i32,
+}
+
+/// Failures sharing a clay, glaze, state or firing.
+#[derive(Serialize)]
+pub(crate) struct DefectGroup {
+    pub(crate) resource: Option<ApiResourceReference>,
+    pub(crate) name: String,
+    pub(crate) failures: i32,
+    pub(crate) defects: Vec<DefectCount>,
+}
+
+/// Works recycled or failed in a firing, broken down by what was lost and
+/// where. Failures are grouped by the state the work had reached, and by
+/// the firing it failed in, if any.
+#[derive(Serialize)]
+pub(crate) struct DefectStats {
+    pub(crate) failures: i32,
+    pub(crate) defects: Vec<DefectCount>,
+    pub(crate) clays: Vec<DefectGroup>,
+    pub(crate) glazes: Vec<DefectGroup>,
+    pub(crate) states: Vec<DefectGroup>,
+    pub(crate) firings: Vec<DefectGroup>,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum NotificationChannel {
+    Email,
+    Webhook,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum NotificationStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Notification {
+    pub(crate) id: i32,
+    /// Identifies what raised the notification, such as a stale work.
+    pub(crate) key: String,
+    pub(crate) channel: NotificationChannel,
+    pub(crate) subject: String,
+    pub(crate) body: String,
+    pub(crate) status: NotificationStatus,
+    pub(crate) attempts: i32,
+    pub(crate) last_error: Option<String>,
+    pub(crate) created_at: NaiveDateTime,
+    pub(crate) sent_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum SearchResource {
+    Work,
+    Project,
+    Glaze,
+}
+
+/// A resource matching a search, with the matches in its name and the best
+/// matching part of its text highlighted. Both are HTML, with the text
+/// escaped and matches wrapped in `<mark>`.
+#[derive(Serialize)]
+pub(crate) struct SearchResult {
+    pub(crate) resource: SearchResource,
+    pub(crate) reference: ApiResourceReference,
+    pub(crate) name: String,
+    pub(crate) snippet: String,
+    /// Higher for better matches.
+    pub(crate) score: f64,
+}
+
+/// FTS5 query matching text containing every word of a search, or words
+/// starting with them, or None if the search has no words. Words are quoted
+/// so that characters the query syntax gives a meaning to are searched for
+/// as they are.
+pub(crate) fn search_query(search: &str) -> Option<String> {
+    let terms = search
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<String>>();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Characters the search index marks the start and end of matches with. They
+/// are from Unicode's private use area, so won't turn up in names or notes.
+pub(crate) const MATCH_START: char = '\u{E000}';
+pub(crate) const MATCH_END: char = '\u{E001}';
+
+/// HTML for text with matches marked by `MATCH_START` and `MATCH_END`,
+/// escaping the text so nothing stored in it is taken as markup.
+pub(crate) fn highlight_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut in_match = false;
+    for c in text.chars() {
+        match c {
+            MATCH_START if !in_match => {
+                html.push_str("<mark>");
+                in_match = true;
+            }
+            MATCH_END if in_match => {
+                html.push_str("</mark>");
+                in_match = false;
+            }
+            MATCH_START | MATCH_END => {}
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    if in_match {
+        html.push_str("</mark>");
+    }
+    html
+}
+
+/// A change to a work or project that registered webhooks are called with.
+#[derive(Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum WebhookEvent {
+    WorkCreated,
+    WorkUpdated,
+    WorkTransitioned,
+    WorkDeleted,
+    ProjectCreated,
+    ProjectUpdated,
+    ProjectDeleted,
+}
+
+#[derive(Serialize, PartialEq, Debug, Clone, Copy, sqlx::Type)]
+pub(crate) enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// An endpoint called on every change. Its secret is never returned.
+#[derive(Serialize)]
+pub(crate) struct WebhookEndpoint {
+    pub(crate) id: i32,
+    pub(crate) url: String,
+    pub(crate) description: Option<String>,
+    pub(crate) is_active: bool,
     pub(crate) created_at: NaiveDateTime,
 }
 
+#[derive(Deserialize, Debug)]
+pub(crate) struct PutWebhookEndpoint {
+    pub(crate) url: String,
+    /// Key the payloads are signed with.
+    pub(crate) secret: String,
+    pub(crate) description: Option<String>,
+    pub(crate) is_active: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct WebhookDelivery {
+    pub(crate) id: i32,
+    pub(crate) event: WebhookEvent,
+    pub(crate) payload: serde_json::Value,
+    pub(crate) status: WebhookDeliveryStatus,
+    pub(crate) attempts: i32,
+    pub(crate) next_attempt_at: Option<NaiveDateTime>,
+    /// HTTP status of the latest response, if the endpoint responded.
+    pub(crate) response_status: Option<i32>,
+    pub(crate) last_error: Option<String>,
+    pub(crate) created_at: NaiveDateTime,
+    pub(crate) delivered_at: Option<NaiveDateTime>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub iat: usize,
@@ -178,60 +1414,369 @@ pub struct LoginUser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::state::load_state_machine;
+    use crate::test_pool;
 
-    #[test]
-    fn test_is_valid_transition() {
-        assert!(is_valid_transition(State::Recycled, State::Thrown));
-        assert!(is_valid_transition(State::Recycled, State::Handbuilt));
+    /// The states and transitions seeded by the migrations.
+    async fn seeded_machine() -> StateMachine {
+        load_state_machine(&test_pool().await).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_is_valid_transition() {
+        let machine = seeded_machine().await;
+
+        assert!(machine.is_valid_transition(1, &State::RECYCLED, &State::THROWN));
+        assert!(machine.is_valid_transition(1, &State::RECYCLED, &State::HANDBUILT));
 
         // Test invalid transitions to Thrown
         let other_states = vec![
-            State::Trimming,
-            State::AwaitingBisqueFiring,
-            State::AwaitingGlazeFiring,
-            State::Finished,
+            State::TRIMMING,
+            State::AWAITING_BISQUE_FIRING,
+            State::AWAITING_GLAZE_FIRING,
+            State::FINISHED,
         ];
         for state in other_states {
-            assert!(!is_valid_transition(state, State::Thrown));
+            assert!(!machine.is_valid_transition(1, &state, &State::THROWN));
         }
 
         // Trimming can only be reached from the thrown state.
-        assert!(is_valid_transition(State::Thrown, State::Trimming));
+        assert!(machine.is_valid_transition(1, &State::THROWN, &State::TRIMMING));
 
         // Bisque can only be reached from Trimming and Handbuilt.
-        assert!(is_valid_transition(
-            State::Trimming,
-            State::AwaitingBisqueFiring
-        ));
+        assert!(machine.is_valid_transition(1, &State::TRIMMING, &State::AWAITING_BISQUE_FIRING));
 
-        assert!(is_valid_transition(
-            State::Handbuilt,
-            State::AwaitingBisqueFiring
-        ));
+        assert!(machine.is_valid_transition(1, &State::HANDBUILT, &State::AWAITING_BISQUE_FIRING));
 
         // Glaze can only be reached from Bisque.
-        assert!(is_valid_transition(
-            State::AwaitingBisqueFiring,
-            State::AwaitingGlazeFiring
+        assert!(machine.is_valid_transition(
+            1,
+            &State::AWAITING_BISQUE_FIRING,
+            &State::AWAITING_GLAZE_FIRING
         ));
 
         // Finished can only be reached from Glaze.
-        assert!(is_valid_transition(
-            State::AwaitingGlazeFiring,
-            State::Finished
-        ));
+        assert!(machine.is_valid_transition(1, &State::AWAITING_GLAZE_FIRING, &State::FINISHED));
 
         // Recycled can be reached from any state except finished and no initial start state.
         let valid_recycled_previous_states = vec![
-            State::Thrown,
-            State::Trimming,
-            State::Handbuilt,
-            State::AwaitingBisqueFiring,
-            State::AwaitingGlazeFiring,
+            State::THROWN,
+            State::TRIMMING,
+            State::HANDBUILT,
+            State::AWAITING_BISQUE_FIRING,
+            State::AWAITING_GLAZE_FIRING,
         ];
         for state in valid_recycled_previous_states {
-            assert!(is_valid_transition(state, State::Recycled));
+            assert!(machine.is_valid_transition(1, &state, &State::RECYCLED));
+        }
+        assert!(!machine.is_valid_transition(1, &State::FINISHED, &State::RECYCLED));
+
+        // Works that failed in a firing can be recycled in any workflow.
+        assert!(machine.is_valid_transition(1, &State::FAILED_IN_FIRING, &State::RECYCLED));
+        assert!(machine.is_valid_transition(5, &State::FAILED_IN_FIRING, &State::RECYCLED));
+    }
+
+    #[tokio::test]
+    async fn test_workflow_transitions() {
+        let machine = seeded_machine().await;
+
+        // Only works following the refire workflow go back into a glaze firing.
+        assert!(!machine.is_valid_transition(1, &State::FINISHED, &State::AWAITING_GLAZE_FIRING));
+        assert!(machine.is_valid_transition(5, &State::FINISHED, &State::AWAITING_GLAZE_FIRING));
+        assert!(machine.is_valid_transition(5, &State::THROWN, &State::TRIMMING));
+
+        // Unknown workflows allow nothing.
+        assert!(!machine.is_valid_transition(9, &State::THROWN, &State::TRIMMING));
+    }
+
+    fn ingredient(material: &str, percentage: f64) -> Ingredient {
+        Ingredient {
+            material: material.to_string(),
+            percentage,
+        }
+    }
+
+    #[test]
+    fn test_recipe_batch() {
+        let recipe = Recipe {
+            ingredients: vec![
+                ingredient("Potash Feldspar", 40.0),
+                ingredient("Silica", 30.0),
+                ingredient("Whiting", 20.0),
+                ingredient("EPK", 10.0),
+            ],
+            additions: vec![ingredient("Red Iron Oxide", 10.0)],
+        };
+        assert!(recipe.is_valid());
+
+        let batch = recipe.batch(5000.0);
+        assert_eq!(batch.ingredients[0].grams, 2000.0);
+        assert_eq!(batch.ingredients[3].grams, 500.0);
+        // Additions are scaled against the base, so sit on top of the batch size.
+        assert_eq!(batch.additions[0].grams, 500.0);
+        assert_eq!(batch.total_grams, 5500.0);
+    }
+
+    #[test]
+    fn test_recipe_validation() {
+        let recipe = Recipe {
+            ingredients: vec![ingredient("Silica", 60.0), ingredient("EPK", 38.5)],
+            additions: vec![ingredient("Rutile", 1.5)],
+        };
+        // Additions do not count towards the base total.
+        assert!(!recipe.is_valid());
+        assert_eq!(recipe.base_total(), 98.5);
+
+        // Summing to 100% is not enough when a percentage is negative.
+        let recipe = Recipe {
+            ingredients: vec![ingredient("Silica", 150.0), ingredient("EPK", -50.0)],
+            additions: vec![],
+        };
+        assert!(!recipe.is_valid());
+        assert_eq!(recipe.invalid_ingredient().unwrap().material, "EPK");
+
+        let recipe = Recipe {
+            ingredients: vec![ingredient("Silica", 100.0)],
+            additions: vec![ingredient("Rutile", f64::NAN)],
+        };
+        assert!(!recipe.is_valid());
+
+        let analysis = OxideAnalysis::from([(Oxide::SiO2, 70.0), (Oxide::Al2O3, -1.0)]);
+        assert_eq!(invalid_oxide(&analysis), Some(Oxide::Al2O3));
+        let analysis = OxideAnalysis::from([(Oxide::SiO2, f64::INFINITY)]);
+        assert_eq!(invalid_oxide(&analysis), Some(Oxide::SiO2));
+        assert_eq!(
+            invalid_oxide(&OxideAnalysis::from([(Oxide::SiO2, 0.0)])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_shrinkage() {
+        assert_eq!(wet_size(9.0, 0.1), 10.0);
+        assert_eq!(fired_size(10.0, 0.1), 9.0);
+        assert_eq!(fired_size(wet_size(12.5, 0.08), 0.08), 12.5);
+
+        // A 350ml mug shrinking by 10% needs to hold 480ml when thrown.
+        assert_eq!(wet_volume(350.0, 0.1).round(), 480.0);
+        assert_eq!(fired_volume(1000.0, 0.1).round(), 729.0);
+    }
+
+    #[test]
+    fn test_cone_rank() {
+        assert!(cone_rank("022") < cone_rank("06"));
+        assert!(cone_rank("06") < cone_rank("01"));
+        assert!(cone_rank("01") < cone_rank("1"));
+        assert!(cone_rank(" 6") < cone_rank("10"));
+        assert_eq!(cone_rank("0"), Some(0));
+        assert_eq!(cone_rank("six"), None);
+        assert_eq!(cone_rank("-6"), None);
+    }
+
+    fn event(state: State, height: Option<f64>, width: Option<f64>) -> Event {
+        Event {
+            id: 0,
+            work: (ApiResource::Work, 1).into(),
+            previous_state: None,
+            current_state: state,
+            occurred_at: NaiveDateTime::default(),
+            created_at: NaiveDateTime::default(),
+            measurements: Some(Measurements {
+                height,
+                width,
+                ..Default::default()
+            }),
+            firing: None,
+            schedule: None,
+            kiln: None,
+            defect: None,
+            defect_note: None,
+            note: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_work_shrinkage() {
+        let events = vec![
+            event(State::THROWN, Some(90.0), Some(90.0)),
+            event(State::RECYCLED, None, None),
+            event(State::THROWN, Some(100.0), Some(80.0)),
+            event(State::TRIMMING, Some(95.0), None),
+            event(State::FINISHED, Some(90.0), None),
+        ];
+
+        // Uses the latest measurements while thrown, ignoring those from before
+        // the work was recycled, and only dimensions measured at both ends.
+        let shrinkage = work_shrinkage(&events).unwrap();
+        assert!((shrinkage.height.unwrap() - 0.1).abs() < 1e-9);
+        assert!(shrinkage.width.is_none());
+
+        // Not yet finished.
+        assert!(work_shrinkage(&events[..4]).is_none());
+    }
+
+    #[test]
+    fn test_schedule_duration() {
+        let segments = vec![
+            Segment {
+                ramp_rate: 100.0,
+                target_temperature: 620.0,
+                hold_minutes: 0,
+            },
+            Segment {
+                ramp_rate: 150.0,
+                target_temperature: 1220.0,
+                hold_minutes: 15,
+            },
+            // Controlled cooling.
+            Segment {
+                ramp_rate: 100.0,
+                target_temperature: 1020.0,
+                hold_minutes: 30,
+            },
+        ];
+
+        assert_eq!(
+            schedule_duration(&segments),
+            360.0 + 240.0 + 15.0 + 120.0 + 30.0
+        );
+        assert_eq!(schedule_duration(&[]), 0.0);
+        assert!(segments.iter().all(Segment::is_valid));
+
+        let segment = |ramp_rate: f64, hold_minutes: i32| Segment {
+            ramp_rate,
+            target_temperature: 1000.0,
+            hold_minutes,
+        };
+        assert!(!segment(0.0, 0).is_valid());
+        assert!(!segment(-50.0, 0).is_valid());
+        assert!(!segment(f64::INFINITY, 0).is_valid());
+        assert!(!segment(100.0, -10).is_valid());
+    }
+
+    fn transition(state: State, defect: Option<Defect>, defect_note: Option<&str>) -> Transition {
+        Transition {
+            state,
+            measurements: None,
+            schedule_id: None,
+            kiln_id: None,
+            defect,
+            defect_note: defect_note.map(|n| n.to_string()),
+            note: None,
+            images: Vec::new(),
+            occurred_at: None,
         }
-        assert!(!is_valid_transition(State::Finished, State::Recycled));
+    }
+
+    #[test]
+    fn test_transition_defect() {
+        assert!(transition(State::TRIMMING, None, None).is_valid_defect());
+        assert!(transition(State::RECYCLED, Some(Defect::SCrack), None).is_valid_defect());
+        assert!(transition(State::FAILED_IN_FIRING, None, Some("cracked")).is_valid_defect());
+
+        // Works that carry on can't have been lost to a defect.
+        assert!(!transition(State::TRIMMING, Some(Defect::SCrack), None).is_valid_defect());
+        assert!(!transition(State::FINISHED, None, Some("crazed")).is_valid_defect());
+    }
+
+    #[test]
+    fn test_glaze_fit() {
+        assert_eq!(glaze_fit(65.0, 62.0), GlazeFit::Ok);
+        assert_eq!(glaze_fit(65.0, 65.0), GlazeFit::Ok);
+        // A glaze that shrinks more than the body on cooling crazes.
+        assert_eq!(glaze_fit(60.0, 70.0), GlazeFit::CrazingRisk);
+        // One that shrinks far less shivers.
+        assert_eq!(glaze_fit(70.0, 55.0), GlazeFit::ShiveringRisk);
+    }
+
+    #[test]
+    fn test_maintenance_counters() {
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2023, 5, d)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        };
+        let fired_at = [day(1), day(3), day(5), day(7)];
+        let history = [
+            (MaintenanceKind::ShelfWash, day(2)),
+            (MaintenanceKind::ShelfWash, day(6)),
+            (MaintenanceKind::ElementReplacement, day(4)),
+        ];
+        let config = MaintenanceConfig {
+            element_replacement: 2,
+            thermocouple_change: 5,
+            shelf_wash: 2,
+            relay_swap: 4,
+        };
+
+        let counters = maintenance_counters(&fired_at, &history, &config);
+        let summary = counters
+            .iter()
+            .map(|c| (c.kind, c.firings_since, c.is_due))
+            .collect::<Vec<(MaintenanceKind, usize, bool)>>();
+        assert_eq!(
+            summary,
+            vec![
+                (MaintenanceKind::ElementReplacement, 2, true),
+                (MaintenanceKind::ThermocoupleChange, 4, false),
+                (MaintenanceKind::ShelfWash, 1, false),
+                (MaintenanceKind::RelaySwap, 4, true),
+            ]
+        );
+        assert_eq!(counters[2].last_performed_at, Some(day(6)));
+        assert_eq!(counters[1].last_performed_at, None);
+    }
+
+    #[test]
+    fn test_staleness() {
+        let transitioned_at = chrono::NaiveDate::from_ymd_opt(2023, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let after = |hours: i64| transitioned_at + chrono::Duration::hours(hours);
+
+        assert_eq!(staleness(transitioned_at, after(48), 3.0), None);
+        assert_eq!(staleness(transitioned_at, after(72), 3.0), None);
+        assert_eq!(staleness(transitioned_at, after(84), 3.0), Some((3.5, 0.5)));
+
+        assert!(is_valid_max_days(0.5));
+        assert!(!is_valid_max_days(0.0));
+        assert!(!is_valid_max_days(-1.0));
+        assert!(!is_valid_max_days(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_search_query() {
+        assert_eq!(
+            search_query("iron  wash"),
+            Some("\"iron\"* \"wash\"*".to_string())
+        );
+        assert_eq!(
+            search_query("\"tenmoku\" OR -x"),
+            Some("\"tenmoku\"* \"OR\"* \"-x\"*".to_string())
+        );
+        assert_eq!(search_query(" \" "), None);
+    }
+
+    #[test]
+    fn test_highlight_html() {
+        assert_eq!(
+            highlight_html("Iron \u{E000}wash\u{E001} & rutile"),
+            "Iron <mark>wash</mark> &amp; rutile"
+        );
+        assert_eq!(
+            highlight_html("\u{E000}<img src=x onerror=alert(1)>\u{E001}"),
+            "<mark>&lt;img src=x onerror=alert(1)&gt;</mark>"
+        );
+        // Stray markers never leave a mark unclosed.
+        assert_eq!(highlight_html("\u{E001}a\u{E000}b"), "a<mark>b</mark>");
+    }
+
+    #[test]
+    fn test_normalise_tags() {
+        let tags = [" Mug", "gift", "", "mug", "Market-Stock "].map(String::from);
+        assert_eq!(normalise_tags(&tags), vec!["gift", "market-stock", "mug"]);
     }
 }
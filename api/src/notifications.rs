@@ -0,0 +1,327 @@
+use chrono::{Datelike, Duration, NaiveDateTime, Utc};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde_json::json;
+use tracing::{event, Level};
+
+use crate::config::{DigestFrequency, NotificationConfig, SmtpConfig, WebhookConfig};
+use crate::handlers::work::{find_stale_works, TIMESTAMP_FORMAT};
+use crate::models::{NotificationChannel, StaleWork};
+use crate::AppState;
+
+/// Attempts made to deliver a notification before giving up on it.
+const MAX_ATTEMPTS: i32 = 5;
+
+struct Reminder {
+    key: String,
+    subject: String,
+    body: String,
+}
+
+/// The last complete day or week before `now`, which a digest covers. Weeks
+/// start on Monday.
+pub(crate) fn digest_period(
+    now: NaiveDateTime,
+    frequency: DigestFrequency,
+) -> (NaiveDateTime, NaiveDateTime) {
+    let today = now.date().and_hms_opt(0, 0, 0).unwrap_or(now);
+    match frequency {
+        DigestFrequency::Daily => (today - Duration::days(1), today),
+        DigestFrequency::Weekly => {
+            let monday = today - Duration::days(now.weekday().num_days_from_monday() as i64);
+            (monday - Duration::weeks(1), monday)
+        }
+    }
+}
+
+fn stale_reminder(work: &StaleWork) -> Reminder {
+    let state = work.current_state.state.key();
+    Reminder {
+        key: format!(
+            "stale:{}:{}",
+            work.work.id,
+            work.current_state.transitioned_at.format(TIMESTAMP_FORMAT)
+        ),
+        subject: format!("{} has been {} for too long", work.name, state),
+        body: format!(
+            "{} has been {} for {:.1} days, {:.1} days longer than it should be.\n{}",
+            work.name, state, work.days_in_state, work.days_overdue, work.work.url
+        ),
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DigestTransitionDTO {
+    name: String,
+    previous_state: Option<String>,
+    current_state: String,
+    occurred_at: NaiveDateTime,
+}
+
+async fn digest_reminder(
+    appstate: &AppState,
+    frequency: DigestFrequency,
+    now: NaiveDateTime,
+) -> Result<Option<Reminder>, sqlx::Error> {
+    let (start, end) = digest_period(now, frequency);
+    let transitions = sqlx::query_as::<_, DigestTransitionDTO>(
+        "SELECT w.name, s1.key AS previous_state, s2.key AS current_state, e.occurred_at
+        FROM events e
+        JOIN works w ON e.work_id = w.id
+        LEFT JOIN states s1 ON e.previous_state = s1.id
+        JOIN states s2 ON e.current_state = s2.id
+        WHERE e.occurred_at >= ? AND e.occurred_at < ?
+        ORDER BY e.occurred_at, e.id",
+    )
+    .bind(start.format(TIMESTAMP_FORMAT).to_string())
+    .bind(end.format(TIMESTAMP_FORMAT).to_string())
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    if transitions.is_empty() {
+        return Ok(None);
+    }
+
+    let body = transitions
+        .iter()
+        .map(|t| match &t.previous_state {
+            Some(previous_state) => format!(
+                "{} {}: {} -> {}",
+                t.occurred_at.format("%Y-%m-%d %H:%M"),
+                t.name,
+                previous_state,
+                t.current_state
+            ),
+            None => format!(
+                "{} {}: created as {}",
+                t.occurred_at.format("%Y-%m-%d %H:%M"),
+                t.name,
+                t.current_state
+            ),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Ok(Some(Reminder {
+        key: format!("digest:{:?}:{}", frequency, start.date()),
+        subject: format!(
+            "{} transitions since {}",
+            transitions.len(),
+            start.format("%Y-%m-%d")
+        ),
+        body,
+    }))
+}
+
+/// Raises a reminder on each channel, unless it has been raised before.
+/// Either every channel gets the reminder or none does.
+async fn raise(
+    appstate: &AppState,
+    channels: &[NotificationChannel],
+    reminder: &Reminder,
+) -> Result<(), sqlx::Error> {
+    let mut tx = appstate.pool.begin().await?;
+    for channel in channels {
+        sqlx::query(
+            "INSERT OR IGNORE INTO notifications (key, channel, subject, body)
+            VALUES (?, ?, ?, ?)",
+        )
+        .bind(&reminder.key)
+        .bind(channel)
+        .bind(&reminder.subject)
+        .bind(&reminder.body)
+        .execute(&mut tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn send_email(config: &SmtpConfig, subject: &str, body: &str) -> Result<(), String> {
+    let parse = |address: &str| address.parse::<Mailbox>().map_err(|e| e.to_string());
+    let mut builder = Message::builder()
+        .from(parse(&config.from)?)
+        .subject(subject);
+    for to in &config.to {
+        builder = builder.to(parse(to)?);
+    }
+    let email = builder.body(body.to_string()).map_err(|e| e.to_string())?;
+
+    let mut transport = if config.tls {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host).map_err(|e| e.to_string())?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+    }
+    .port(config.port);
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    transport
+        .build()
+        .send(email)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn send_webhook(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    key: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), String> {
+    client
+        .post(&config.url)
+        .json(&json!({ "key": key, "subject": subject, "body": body }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(sqlx::FromRow)]
+struct PendingDTO {
+    id: i32,
+    key: String,
+    channel: NotificationChannel,
+    subject: String,
+    body: String,
+}
+
+/// Attempts to deliver every pending notification, giving up on those that
+/// have failed too many times.
+async fn deliver_pending(
+    appstate: &AppState,
+    config: &NotificationConfig,
+    client: &reqwest::Client,
+) -> Result<(), sqlx::Error> {
+    let pending = sqlx::query_as::<_, PendingDTO>(
+        "SELECT id, key, channel, subject, body FROM notifications WHERE status = 'Pending' ORDER BY id",
+    )
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    for notification in pending {
+        let result = match (notification.channel, &config.smtp, &config.webhook) {
+            (NotificationChannel::Email, Some(smtp), _) => {
+                send_email(smtp, &notification.subject, &notification.body).await
+            }
+            (NotificationChannel::Webhook, _, Some(webhook)) => {
+                send_webhook(
+                    client,
+                    webhook,
+                    &notification.key,
+                    &notification.subject,
+                    &notification.body,
+                )
+                .await
+            }
+            _ => Err("channel is no longer configured".to_string()),
+        };
+
+        match result {
+            Ok(()) => {
+                sqlx::query(
+                    "UPDATE notifications
+                    SET status = 'Sent', attempts = attempts + 1, last_error = NULL,
+                    sent_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+                    WHERE id = ?",
+                )
+                .bind(notification.id)
+                .execute(&appstate.pool)
+                .await?;
+            }
+            Err(e) => {
+                event!(Level::WARN, source = "Notifications", id = notification.id, err = %e);
+                sqlx::query(
+                    "UPDATE notifications
+                    SET attempts = attempts + 1, last_error = ?,
+                    status = CASE WHEN attempts + 1 >= ? THEN 'Failed' ELSE 'Pending' END
+                    WHERE id = ?",
+                )
+                .bind(e)
+                .bind(MAX_ATTEMPTS)
+                .bind(notification.id)
+                .execute(&appstate.pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn evaluate(
+    appstate: &AppState,
+    config: &NotificationConfig,
+    client: &reqwest::Client,
+) -> Result<(), sqlx::Error> {
+    let mut channels = Vec::new();
+    if config.smtp.is_some() {
+        channels.push(NotificationChannel::Email);
+    }
+    if config.webhook.is_some() {
+        channels.push(NotificationChannel::Webhook);
+    }
+
+    if config.stale_works {
+        for work in find_stale_works(appstate).await? {
+            raise(appstate, &channels, &stale_reminder(&work)).await?;
+        }
+    }
+
+    if let Some(frequency) = config.digest {
+        let now = Utc::now().naive_utc();
+        if let Some(reminder) = digest_reminder(appstate, frequency, now).await? {
+            raise(appstate, &channels, &reminder).await?;
+        }
+    }
+
+    deliver_pending(appstate, config, client).await
+}
+
+/// Evaluates the reminder rules and delivers the notifications they raise,
+/// every `interval` seconds for as long as the server runs.
+pub(crate) async fn run(appstate: AppState, config: NotificationConfig) {
+    let client = reqwest::Client::new();
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(config.interval.get()));
+    loop {
+        interval.tick().await;
+        if let Err(e) = evaluate(&appstate, &config, &client).await {
+            event!(Level::ERROR, source = "Notifications", err = ?e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_period() {
+        let at = |d: u32, h: u32| {
+            chrono::NaiveDate::from_ymd_opt(2023, 5, d)
+                .unwrap()
+                .and_hms_opt(h, 0, 0)
+                .unwrap()
+        };
+
+        // 10 May 2023 was a Wednesday.
+        assert_eq!(
+            digest_period(at(10, 15), DigestFrequency::Daily),
+            (at(9, 0), at(10, 0))
+        );
+        assert_eq!(
+            digest_period(at(10, 15), DigestFrequency::Weekly),
+            (at(1, 0), at(8, 0))
+        );
+        assert_eq!(
+            digest_period(at(8, 0), DigestFrequency::Weekly),
+            (at(1, 0), at(8, 0))
+        );
+    }
+}
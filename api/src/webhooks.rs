@@ -0,0 +1,200 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::{Executor, Sqlite};
+use tracing::{event, Level};
+
+use crate::handlers::work::TIMESTAMP_FORMAT;
+use crate::models::{ApiResourceReference, WebhookEvent};
+use crate::AppState;
+
+/// Attempts made to deliver a payload before giving up on it.
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Seconds between checks for deliveries that are due.
+const INTERVAL: u64 = 5;
+
+/// Queues a delivery of the event to every active endpoint. Takes the
+/// executor the change was made with, so nothing is queued for a change
+/// that is rolled back.
+pub(crate) async fn enqueue<'c, E>(
+    executor: E,
+    event: WebhookEvent,
+    resource: ApiResourceReference,
+    data: serde_json::Value,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let payload = json!({
+        "event": event,
+        "resource": resource,
+        "data": data,
+        "occurred_at": Utc::now().naive_utc().format(TIMESTAMP_FORMAT).to_string(),
+    });
+
+    sqlx::query(
+        "INSERT INTO webhook_deliveries (endpoint_id, event, payload)
+        SELECT id, ?, ? FROM webhook_endpoints WHERE is_active",
+    )
+    .bind(event)
+    .bind(payload.to_string())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Hex encoded HMAC-SHA256 of the payload, which endpoints can recompute
+/// with their secret to check a call came from us.
+pub(crate) fn sign(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Time to wait before retrying a delivery that has failed `attempts`
+/// times, doubling from 30 seconds up to 6 hours.
+pub(crate) fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.clamp(1, 16) - 1;
+    Duration::seconds(30 * 2i64.pow(exponent as u32)).min(Duration::hours(6))
+}
+
+#[derive(sqlx::FromRow)]
+struct DueDeliveryDTO {
+    id: i32,
+    event: WebhookEvent,
+    payload: String,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+async fn send(
+    client: &reqwest::Client,
+    delivery: &DueDeliveryDTO,
+) -> (Option<i32>, Option<String>) {
+    let response = client
+        .post(&delivery.url)
+        .header("Content-Type", "application/json")
+        .header("X-Wyrhta-Event", format!("{:?}", delivery.event))
+        .header("X-Wyrhta-Delivery", delivery.id.to_string())
+        .header(
+            "X-Wyrhta-Signature",
+            format!("sha256={}", sign(&delivery.secret, &delivery.payload)),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            let error = (!status.is_success()).then(|| format!("endpoint responded {}", status));
+            (Some(status.as_u16() as i32), error)
+        }
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Attempts every delivery that is due, rescheduling those that fail until
+/// they have failed too many times.
+async fn deliver_due(appstate: &AppState, client: &reqwest::Client) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let due = sqlx::query_as::<_, DueDeliveryDTO>(
+        "SELECT d.id, d.event, d.payload, d.attempts, e.url, e.secret
+        FROM webhook_deliveries d
+        JOIN webhook_endpoints e ON d.endpoint_id = e.id
+        WHERE d.status = 'Pending' AND d.next_attempt_at <= ?
+        ORDER BY d.id",
+    )
+    .bind(now.format(TIMESTAMP_FORMAT).to_string())
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    for delivery in due {
+        let (response_status, error) = send(client, &delivery).await;
+        let attempts = delivery.attempts + 1;
+
+        match error {
+            None => {
+                sqlx::query(
+                    "UPDATE webhook_deliveries
+                    SET status = 'Delivered', attempts = ?, response_status = ?, last_error = NULL,
+                    delivered_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+                    WHERE id = ?",
+                )
+                .bind(attempts)
+                .bind(response_status)
+                .bind(delivery.id)
+                .execute(&appstate.pool)
+                .await?;
+            }
+            Some(e) => {
+                event!(Level::WARN, source = "Webhooks", id = delivery.id, err = %e);
+                let status = if attempts >= MAX_ATTEMPTS {
+                    "Failed"
+                } else {
+                    "Pending"
+                };
+                let next_attempt_at: NaiveDateTime = Utc::now().naive_utc() + backoff(attempts);
+                sqlx::query(
+                    "UPDATE webhook_deliveries
+                    SET status = ?, attempts = ?, response_status = ?, last_error = ?,
+                    next_attempt_at = ?
+                    WHERE id = ?",
+                )
+                .bind(status)
+                .bind(attempts)
+                .bind(response_status)
+                .bind(e)
+                .bind(next_attempt_at.format(TIMESTAMP_FORMAT).to_string())
+                .bind(delivery.id)
+                .execute(&appstate.pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Delivers queued webhook payloads for as long as the server runs.
+pub(crate) async fn run(appstate: AppState) {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(INTERVAL));
+    loop {
+        interval.tick().await;
+        if let Err(e) = deliver_due(&appstate, &client).await {
+            event!(Level::ERROR, source = "Webhooks", err = ?e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::seconds(30));
+        assert_eq!(backoff(2), Duration::seconds(60));
+        assert_eq!(backoff(5), Duration::seconds(480));
+        assert_eq!(backoff(12), Duration::hours(6));
+        assert_eq!(backoff(100), Duration::hours(6));
+    }
+}